@@ -100,6 +100,7 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 			spawner: &task_manager.spawn_essential_handle(),
 			can_author_with: sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone()),
 			registry: config.prometheus_registry(),
+			import_time_buckets: Default::default(),
 			check_for_equivocation: Default::default(),
 			telemetry: telemetry.as_ref().map(|x| x.handle()),
 		},
@@ -364,6 +365,7 @@ pub fn new_light(mut config: Configuration) -> Result<TaskManager, ServiceError>
 			spawner: &task_manager.spawn_essential_handle(),
 			can_author_with: sp_consensus::NeverCanAuthor,
 			registry: config.prometheus_registry(),
+			import_time_buckets: Default::default(),
 			check_for_equivocation: Default::default(),
 			telemetry: telemetry.as_ref().map(|x| x.handle()),
 		},
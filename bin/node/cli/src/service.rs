@@ -133,6 +133,7 @@ pub fn new_partial(
 		},
 		&task_manager.spawn_essential_handle(),
 		config.prometheus_registry(),
+		&Default::default(),
 		sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone()),
 		telemetry.as_ref().map(|x| x.handle()),
 	)?;
@@ -512,6 +513,7 @@ pub fn new_light_base(
 		},
 		&task_manager.spawn_essential_handle(),
 		config.prometheus_registry(),
+		&Default::default(),
 		sp_consensus::NeverCanAuthor,
 		telemetry.as_ref().map(|x| x.handle()),
 	)?;
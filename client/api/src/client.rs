@@ -176,6 +176,8 @@ pub struct StateDbMemoryInfo {
 	pub pruning: Option<MemorySize>,
 	/// Memory usage of the pinned blocks.
 	pub pinned: MemorySize,
+	/// Number of pinned blocks.
+	pub pinned_blocks: u64,
 }
 
 /// Memory statistics for client instance.
@@ -27,11 +27,12 @@ use crate::params::TransactionPoolParams;
 use crate::CliConfiguration;
 use regex::Regex;
 use sc_service::{
-	config::{BasePath, PrometheusConfig, TransactionPoolOptions},
+	config::{BasePath, MetricFamily, PrometheusConfig, TransactionPoolOptions},
 	ChainSpec, Role,
 };
 use sc_telemetry::TelemetryEndpoints;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 /// The `run` command used to run a node.
@@ -141,6 +142,25 @@ pub struct RunCmd {
 	#[structopt(long = "no-prometheus")]
 	pub no_prometheus: bool,
 
+	/// Do not update the given Prometheus metric families, e.g. `number_leaves`.
+	///
+	/// The families are left out of the Prometheus endpoint.
+	#[structopt(long = "prometheus-disable-metric", value_name = "NAME")]
+	pub prometheus_disabled_metrics: Vec<MetricFamily>,
+
+	/// Serve the Prometheus metrics on a Unix domain socket at the given path instead of the
+	/// Prometheus port.
+	///
+	/// Only supported on Unix platforms.
+	#[structopt(long = "prometheus-socket", value_name = "PATH", parse(from_os_str))]
+	pub prometheus_socket: Option<PathBuf>,
+
+	/// Do not expose the Prometheus endpoint.
+	///
+	/// The registered metric families and their values are logged once instead.
+	#[structopt(long = "prometheus-dry-run")]
+	pub prometheus_dry_run: bool,
+
 	/// The human-readable name for this node.
 	///
 	/// The node name will be reported to the telemetry server, if enabled.
@@ -359,12 +379,16 @@ impl CliConfiguration for RunCmd {
 				Ipv4Addr::LOCALHOST
 			};
 
-			Some(PrometheusConfig::new_with_default_registry(
+			let mut config = PrometheusConfig::new_with_default_registry(
 				SocketAddr::new(
 					interface.into(),
 					self.prometheus_port.unwrap_or(default_listen_port),
 				)
-			))
+			);
+			config.disabled_metrics = self.prometheus_disabled_metrics.iter().copied().collect();
+			config.dry_run = self.prometheus_dry_run;
+			config.unix_socket = self.prometheus_socket.clone();
+			Some(config)
 		})
 	}
 
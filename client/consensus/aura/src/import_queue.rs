@@ -29,7 +29,7 @@ use sp_consensus::{
 	BlockImport, CanAuthorWith, ForkChoiceStrategy, BlockImportParams,
 	BlockOrigin, Error as ConsensusError,
 	import_queue::{
-		Verifier, BasicQueue, DefaultImportQueue, BoxJustificationImport,
+		Verifier, BasicQueue, DefaultImportQueue, BoxJustificationImport, ImportTimeBuckets,
 	},
 };
 use sc_client_api::{BlockOf, UsageProvider, backend::AuxStore};
@@ -392,6 +392,8 @@ pub struct ImportQueueParams<'a, Block, I, C, S, CAW, CIDP> {
 	pub spawner: &'a S,
 	/// The prometheus registry.
 	pub registry: Option<&'a Registry>,
+	/// The histogram buckets to report the block import times with.
+	pub import_time_buckets: ImportTimeBuckets,
 	/// Can we author with the current node?
 	pub can_author_with: CAW,
 	/// Should we check for equivocation?
@@ -409,6 +411,7 @@ pub fn import_queue<'a, P, Block, I, C, S, CAW, CIDP>(
 		create_inherent_data_providers,
 		spawner,
 		registry,
+		import_time_buckets,
 		can_author_with,
 		check_for_equivocation,
 		telemetry,
@@ -450,12 +453,13 @@ pub fn import_queue<'a, P, Block, I, C, S, CAW, CIDP>(
 		},
 	);
 
-	Ok(BasicQueue::new(
+	Ok(BasicQueue::with_import_time_buckets(
 		verifier,
 		Box::new(block_import),
 		justification_import,
 		spawner,
 		registry,
+		&import_time_buckets,
 	))
 }
 
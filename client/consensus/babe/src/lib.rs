@@ -94,7 +94,8 @@ use sc_telemetry::{telemetry, TelemetryHandle, CONSENSUS_TRACE, CONSENSUS_DEBUG}
 use sp_consensus::{
 	BlockImport, Environment, Proposer, BlockCheckParams,
 	ForkChoiceStrategy, BlockImportParams, BlockOrigin, Error as ConsensusError,
-	SelectChain, SlotData, import_queue::{Verifier, BasicQueue, DefaultImportQueue, CacheKeyId},
+	SelectChain, SlotData,
+	import_queue::{Verifier, BasicQueue, DefaultImportQueue, CacheKeyId, ImportTimeBuckets},
 };
 use sp_consensus_babe::inherents::BabeInherentData;
 use sc_client_api::{
@@ -1599,6 +1600,7 @@ pub fn import_queue<Block: BlockT, Client, SelectChain, Inner, CAW, CIDP>(
 	create_inherent_data_providers: CIDP,
 	spawner: &impl sp_core::traits::SpawnEssentialNamed,
 	registry: Option<&Registry>,
+	import_time_buckets: &ImportTimeBuckets,
 	can_author_with: CAW,
 	telemetry: Option<TelemetryHandle>,
 ) -> ClientResult<DefaultImportQueue<Block, Client>> where
@@ -1623,11 +1625,12 @@ pub fn import_queue<Block: BlockT, Client, SelectChain, Inner, CAW, CIDP>(
 		client,
 	};
 
-	Ok(BasicQueue::new(
+	Ok(BasicQueue::with_import_time_buckets(
 		verifier,
 		Box::new(block_import),
 		justification_import,
 		spawner,
 		registry,
+		import_time_buckets,
 	))
 }
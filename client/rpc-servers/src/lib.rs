@@ -115,16 +115,20 @@ mod inner {
 
 	/// Start WS server listening on given address.
 	///
+	/// Open connections are reported to `metrics`.
+	///
 	/// **Note**: Only available if `not(target_os = "unknown")`.
 	pub fn start_ws<M: pubsub::PubSubMetadata + From<jsonrpc_core::futures::sync::mpsc::Sender<String>>> (
 		addr: &std::net::SocketAddr,
 		max_connections: Option<usize>,
 		cors: Option<&Vec<String>>,
 		io: RpcHandler<M>,
+		metrics: RpcMetrics,
 	) -> io::Result<ws::Server> {
 		ws::ServerBuilder::with_meta_extractor(io, |context: &ws::RequestContext| context.sender().into())
 			.max_payload(MAX_PAYLOAD)
 			.max_connections(max_connections.unwrap_or(WS_MAX_CONNECTIONS))
+			.session_stats(metrics)
 			.allowed_origins(map_cors(cors))
 			.allowed_hosts(hosts_filtering(cors.is_some()))
 			.start(addr)
@@ -20,19 +20,24 @@
 
 use jsonrpc_core::{
 	Middleware as RequestMiddleware, Metadata,
-	Request, Response, FutureResponse, FutureOutput
+	Request, Response, FutureResponse, FutureOutput,
+	Call, ErrorCode, Output,
 };
 use prometheus_endpoint::{
-	Registry, CounterVec, PrometheusError,
-	Opts, register, U64
+	Registry, CounterVec, Gauge, HistogramOpts, HistogramVec, PrometheusError,
+	Opts, exponential_buckets, register, U64
 };
 
 use futures::{future::Either, Future};
+use wasm_timer::Instant;
 
 /// Metrics for RPC middleware
 #[derive(Debug, Clone)]
 pub struct RpcMetrics {
 	rpc_calls: Option<CounterVec<U64>>,
+	rpc_calls_time: Option<HistogramVec>,
+	rpc_connections: Option<Gauge<U64>>,
+	rpc_subscriptions: Option<Gauge<U64>>,
 }
 
 impl RpcMetrics {
@@ -51,8 +56,58 @@ impl RpcMetrics {
 					r,
 				)
 			).transpose()?,
+			rpc_calls_time: metrics_registry.map(|r|
+				register(
+					HistogramVec::new(
+						HistogramOpts::new(
+							"rpc_request_duration_seconds",
+							"Time taken to answer rpc calls, by method",
+						).buckets(exponential_buckets(0.001, 4.0, 9)?),
+						&["protocol", "method"]
+					)?,
+					r,
+				)
+			).transpose()?,
+			rpc_connections: metrics_registry.map(|r|
+				register(
+					Gauge::new(
+						"rpc_connections",
+						"Number of open WebSocket rpc connections",
+					)?,
+					r,
+				)
+			).transpose()?,
+			rpc_subscriptions: metrics_registry.map(|r|
+				register(
+					Gauge::new(
+						"rpc_subscriptions",
+						"Number of active rpc subscriptions",
+					)?,
+					r,
+				)
+			).transpose()?,
 		})
 	}
+
+	/// The gauge to report the number of active subscriptions to, if metrics are enabled.
+	pub fn subscriptions_gauge(&self) -> Option<Gauge<U64>> {
+		self.rpc_subscriptions.clone()
+	}
+}
+
+#[cfg(not(target_os = "unknown"))]
+impl ws::SessionStats for RpcMetrics {
+	fn open_session(&self, _id: ws::SessionId) {
+		if let Some(ref rpc_connections) = self.rpc_connections {
+			rpc_connections.inc();
+		}
+	}
+
+	fn close_session(&self, _id: ws::SessionId) {
+		if let Some(ref rpc_connections) = self.rpc_connections {
+			rpc_connections.dec();
+		}
+	}
 }
 
 /// Middleware for RPC calls
@@ -72,6 +127,11 @@ impl RpcMiddleware {
 			transport_label: String::from(transport_label),
 		}
 	}
+
+	/// The metrics that statistics are reported to.
+	pub fn metrics(&self) -> &RpcMetrics {
+		&self.metrics
+	}
 }
 
 impl<M: Metadata> RequestMiddleware<M> for RpcMiddleware {
@@ -87,6 +147,134 @@ impl<M: Metadata> RequestMiddleware<M> for RpcMiddleware {
 			rpc_calls.with_label_values(&[self.transport_label.as_str()]).inc();
 		}
 
+		if let Some(rpc_calls_time) = self.metrics.rpc_calls_time.clone() {
+			let method = method_label(&request);
+			let transport_label = self.transport_label.clone();
+			let started = Instant::now();
+			return Either::A(Box::new(next(request, meta).map(move |response| {
+				// Only methods that answered are known to exist. The others, including any
+				// notification, are grouped together to keep the number of series bounded.
+				let method = if is_answered(&response) { method.as_str() } else { "other" };
+				rpc_calls_time.with_label_values(&[transport_label.as_str(), method])
+					.observe(started.elapsed().as_secs_f64());
+				response
+			})))
+		}
+
 		Either::B(next(request, meta))
 	}
 }
+
+/// Returns the method called by `request`, or `batch` if it contains several calls.
+fn method_label(request: &Request) -> String {
+	match request {
+		Request::Single(Call::MethodCall(call)) => call.method.clone(),
+		Request::Single(Call::Notification(notification)) => notification.method.clone(),
+		Request::Single(Call::Invalid { .. }) => "other".into(),
+		Request::Batch(_) => "batch".into(),
+	}
+}
+
+/// Returns whether `response` was given by an existing method.
+fn is_answered(response: &Option<Response>) -> bool {
+	match response {
+		Some(Response::Single(Output::Failure(failure))) =>
+			failure.error.code != ErrorCode::MethodNotFound,
+		Some(_) => true,
+		None => false,
+	}
+}
+
+#[cfg(all(test, not(target_os = "unknown")))]
+mod tests {
+	use super::*;
+	use ws::SessionStats;
+
+	#[test]
+	fn request_durations_are_labeled_by_method() {
+		let registry = Registry::new();
+		let middleware = RpcMiddleware::new(RpcMetrics::new(Some(&registry)).unwrap(), "http");
+		let send = |request: String| {
+			let request = serde_json::from_str(&request).unwrap();
+			let response = middleware.on_request(request, (), |request, _| {
+				let known = match &request {
+					Request::Single(Call::MethodCall(call)) => call.method == "system_name",
+					// Notifications are never answered.
+					_ => return futures::future::ok(None),
+				};
+				let output = if known {
+					Output::Success(jsonrpc_core::Success {
+						jsonrpc: Some(jsonrpc_core::Version::V2),
+						result: "node".into(),
+						id: jsonrpc_core::Id::Num(1),
+					})
+				} else {
+					Output::Failure(jsonrpc_core::Failure {
+						jsonrpc: Some(jsonrpc_core::Version::V2),
+						error: jsonrpc_core::Error::method_not_found(),
+						id: jsonrpc_core::Id::Num(1),
+					})
+				};
+				futures::future::ok(Some(Response::Single(output)))
+			});
+			response.wait().unwrap();
+		};
+		let call = |method| send(format!(r#"{{"jsonrpc":"2.0","method":"{}","id":1}}"#, method));
+		let notify = |method| send(format!(r#"{{"jsonrpc":"2.0","method":"{}"}}"#, method));
+
+		call("system_name");
+		call("system_name");
+		call("made_up_1");
+		call("made_up_2");
+		notify("made_up_3");
+		notify("system_name");
+
+		let counts = registry.gather().into_iter()
+			.find(|family| family.get_name() == "rpc_request_duration_seconds")
+			.unwrap()
+			.get_metric()
+			.iter()
+			.map(|metric| {
+				let method = metric.get_label().iter()
+					.find(|pair| pair.get_name() == "method")
+					.unwrap()
+					.get_value()
+					.to_string();
+				assert_eq!(metric.get_histogram().get_bucket().len(), 9);
+				(method, metric.get_histogram().get_sample_count())
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(counts, vec![("other".to_string(), 4), ("system_name".to_string(), 2)]);
+	}
+
+	#[test]
+	fn ws_sessions_are_counted() {
+		let registry = Registry::new();
+		let metrics = RpcMetrics::new(Some(&registry)).unwrap();
+
+		metrics.open_session(1);
+		metrics.open_session(2);
+		metrics.close_session(1);
+
+		let connections = registry.gather().into_iter()
+			.find(|family| family.get_name() == "rpc_connections")
+			.map(|family| family.get_metric()[0].get_gauge().get_value());
+		assert_eq!(connections, Some(1.0));
+	}
+
+	#[test]
+	fn subscriptions_are_reported() {
+		let registry = Registry::new();
+		let metrics = RpcMetrics::new(Some(&registry)).unwrap();
+		let gauge = metrics.subscriptions_gauge().unwrap();
+
+		gauge.add(3);
+		gauge.dec();
+
+		let subscriptions = registry.gather().into_iter()
+			.find(|family| family.get_name() == "rpc_subscriptions")
+			.map(|family| family.get_metric()[0].get_gauge().get_value());
+		assert_eq!(subscriptions, Some(2.0));
+		assert!(RpcMetrics::new(None).unwrap().subscriptions_gauge().is_none());
+	}
+}
@@ -23,6 +23,7 @@
 #![warn(missing_docs)]
 
 use futures::{compat::Future01CompatExt, FutureExt};
+use prometheus_endpoint::{Gauge, U64};
 use rpc::futures::future::{Executor, ExecuteError, Future};
 use sp_core::traits::SpawnNamed;
 use std::sync::Arc;
@@ -41,12 +42,21 @@ pub mod testing;
 
 /// Task executor that is being used by RPC subscriptions.
 #[derive(Clone)]
-pub struct SubscriptionTaskExecutor(Arc<dyn SpawnNamed>);
+pub struct SubscriptionTaskExecutor {
+	spawn: Arc<dyn SpawnNamed>,
+	active_subscriptions: Option<Gauge<U64>>,
+}
 
 impl SubscriptionTaskExecutor {
 	/// Create a new `Self` with the given spawner.
 	pub fn new(spawn: impl SpawnNamed + 'static) -> Self {
-		Self(Arc::new(spawn))
+		Self { spawn: Arc::new(spawn), active_subscriptions: None }
+	}
+
+	/// Report the number of running subscription tasks to the given gauge.
+	pub fn with_subscriptions_gauge(mut self, gauge: Option<Gauge<U64>>) -> Self {
+		self.active_subscriptions = gauge;
+		self
 	}
 }
 
@@ -55,7 +65,73 @@ impl Executor<Box<dyn Future<Item = (), Error = ()> + Send>> for SubscriptionTas
 		&self,
 		future: Box<dyn Future<Item = (), Error = ()> + Send>,
 	) -> Result<(), ExecuteError<Box<dyn Future<Item = (), Error = ()> + Send>>> {
-		self.0.spawn("substrate-rpc-subscription", future.compat().map(drop).boxed());
+		let active = self.active_subscriptions.clone().map(ActiveSubscription::new);
+		let future = future.compat().map(move |_| drop(active));
+		self.spawn.spawn("substrate-rpc-subscription", future.boxed());
 		Ok(())
 	}
 }
+
+/// Counts a subscription task in a gauge for as long as it is alive.
+///
+/// The task may be dropped before completing, e.g. when the node shuts down, so the gauge is
+/// decremented on drop rather than on completion.
+struct ActiveSubscription(Gauge<U64>);
+
+impl ActiveSubscription {
+	fn new(gauge: Gauge<U64>) -> Self {
+		gauge.inc();
+		Self(gauge)
+	}
+}
+
+impl Drop for ActiveSubscription {
+	fn drop(&mut self) {
+		self.0.dec();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::future::BoxFuture;
+	use parking_lot::Mutex;
+	use prometheus_endpoint::{Registry, register};
+	use rpc::futures::future;
+
+	/// Keeps spawned tasks around so that the test decides when they run.
+	#[derive(Clone, Default)]
+	struct Tasks(Arc<Mutex<Vec<BoxFuture<'static, ()>>>>);
+
+	impl SpawnNamed for Tasks {
+		fn spawn_blocking(&self, _: &'static str, future: BoxFuture<'static, ()>) {
+			self.0.lock().push(future);
+		}
+
+		fn spawn(&self, _: &'static str, future: BoxFuture<'static, ()>) {
+			self.0.lock().push(future);
+		}
+	}
+
+	#[test]
+	fn running_subscriptions_are_counted() {
+		let registry = Registry::new();
+		let gauge = register(Gauge::new("rpc_subscriptions", "Subscriptions").unwrap(), &registry)
+			.unwrap();
+		let tasks = Tasks::default();
+		let executor = SubscriptionTaskExecutor::new(tasks.clone())
+			.with_subscriptions_gauge(Some(gauge.clone()));
+
+		executor.execute(Box::new(future::ok::<(), ()>(()))).unwrap();
+		executor.execute(Box::new(future::empty::<(), ()>())).unwrap();
+		assert_eq!(gauge.get(), 2);
+
+		let mut spawned = std::mem::take(&mut *tasks.0.lock());
+		futures::executor::block_on(spawned.remove(0));
+		assert_eq!(gauge.get(), 1);
+
+		// A subscription that is cancelled instead of completing is not counted anymore either.
+		drop(spawned);
+		assert_eq!(gauge.get(), 0);
+	}
+}
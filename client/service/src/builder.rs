@@ -19,7 +19,7 @@
 use crate::{
 	error::Error, MallocSizeOfWasm, RpcHandlers,
 	start_rpc_servers, build_network_future, TransactionPoolAdapter, TaskManager, SpawnTaskHandle,
-	metrics::MetricsService,
+	metrics::{MetricsHandle, MetricsService},
 	client::{light, Client, ClientConfig},
 	config::{Configuration, KeystoreConfig, PrometheusConfig},
 };
@@ -576,6 +576,32 @@ pub fn spawn_tasks<TBl, TBackend, TExPool, TRpc, TCl>(
 		TExPool: MaintainedTransactionPool<Block=TBl, Hash = <TBl as BlockT>::Hash> +
 			MallocSizeOfWasm + 'static,
 		TRpc: sc_rpc::RpcExtension<sc_rpc::Metadata>
+{
+	spawn_tasks_with_metrics(params, MetricsHandle::default())
+}
+
+/// Like [`spawn_tasks`], but the periodic metrics updates report to `metrics`, e.g. to read
+/// their values from an RPC or to reconfigure them while the node runs.
+pub fn spawn_tasks_with_metrics<TBl, TBackend, TExPool, TRpc, TCl>(
+	params: SpawnTasksParams<TBl, TCl, TExPool, TRpc, TBackend>,
+	metrics_handle: MetricsHandle,
+) -> Result<RpcHandlers, Error>
+	where
+		TCl: ProvideRuntimeApi<TBl> + HeaderMetadata<TBl, Error=sp_blockchain::Error> + Chain<TBl> +
+		BlockBackend<TBl> + BlockIdTo<TBl, Error=sp_blockchain::Error> + ProofProvider<TBl> +
+		HeaderBackend<TBl> + BlockchainEvents<TBl> + ExecutorProvider<TBl> + UsageProvider<TBl> +
+		StorageProvider<TBl, TBackend> + CallApiAt<TBl> + Send + 'static,
+		<TCl as ProvideRuntimeApi<TBl>>::Api:
+			sp_api::Metadata<TBl> +
+			sc_offchain::OffchainWorkerApi<TBl> +
+			sp_transaction_pool::runtime_api::TaggedTransactionQueue<TBl> +
+			sp_session::SessionKeys<TBl> +
+			sp_api::ApiExt<TBl, StateBackend = TBackend::State>,
+		TBl: BlockT,
+		TBackend: 'static + sc_client_api::backend::Backend<TBl> + Send,
+		TExPool: MaintainedTransactionPool<Block=TBl, Hash = <TBl as BlockT>::Hash> +
+			MallocSizeOfWasm + 'static,
+		TRpc: sc_rpc::RpcExtension<sc_rpc::Metadata>
 {
 	let SpawnTasksParams {
 		mut config,
@@ -631,20 +657,53 @@ pub fn spawn_tasks<TBl, TBackend, TExPool, TRpc, TCl>(
 	);
 
 	// Prometheus metrics.
-	let metrics_service = if let Some(PrometheusConfig { port, registry }) =
-		config.prometheus_config.clone()
-	{
+	let metrics_service = if let Some(PrometheusConfig {
+		port,
+		registry,
+		disabled_metrics,
+		unix_socket,
+		dry_run,
+	}) = config.prometheus_config.clone() {
 		// Set static metrics.
-		let metrics = MetricsService::with_prometheus(telemetry.clone(), &registry, &config)?;
-		spawn_handle.spawn(
-			"prometheus-endpoint",
-			prometheus_endpoint::init_prometheus(port, registry).map(drop)
-		);
+		let metrics = MetricsService::with_prometheus(
+			telemetry.clone(),
+			&registry,
+			&config,
+			&format!("{:?}", chain_info.genesis_hash),
+		)?;
+		for family in disabled_metrics {
+			metrics.handle().disable_family(family)?;
+		}
 
-		metrics
+		if dry_run {
+			metrics.with_dry_run()
+		} else {
+			let endpoint = match unix_socket {
+				#[cfg(unix)]
+				Some(path) => prometheus_endpoint::init_prometheus_unix(path, registry)
+					.map(|result| if let Err(err) = result {
+						log::error!("Prometheus endpoint stopped: {}", err);
+					})
+					.boxed(),
+				#[cfg(not(unix))]
+				Some(path) => return Err(Error::Other(format!(
+					"Prometheus Unix sockets aren't supported on this platform: {}",
+					path.display(),
+				))),
+				None => prometheus_endpoint::init_prometheus(port, registry).map(drop).boxed(),
+			};
+			spawn_handle.spawn("prometheus-endpoint", endpoint);
+
+			metrics
+		}
 	} else {
 		MetricsService::new(telemetry.clone())
 	};
+	#[cfg(not(target_os = "unknown"))]
+	metrics_handle.register_malloc_size_of("transaction_pool", transaction_pool.clone());
+	let metrics_service = metrics_service
+		.with_telemetry_fields(config.telemetry_fields)
+		.with_handle(metrics_handle);
 
 	// Periodically updated metrics and telemetry updates.
 	spawn_handle.spawn("telemetry-periodic-send",
@@ -776,7 +835,8 @@ fn gen_handler<TBl, TBackend, TExPool, TRpc, TCl>(
 		chain_type: config.chain_spec.chain_type(),
 	};
 
-	let task_executor = sc_rpc::SubscriptionTaskExecutor::new(spawn_handle);
+	let task_executor = sc_rpc::SubscriptionTaskExecutor::new(spawn_handle)
+		.with_subscriptions_gauge(rpc_middleware.metrics().subscriptions_gauge());
 	let subscriptions = SubscriptionManager::new(Arc::new(task_executor.clone()));
 
 	let (chain, state, child_state) = if let (Some(remote_blockchain), Some(on_demand)) =
@@ -90,7 +90,7 @@ use sc_client_api::{
 };
 use sp_utils::mpsc::{TracingUnboundedSender, tracing_unbounded};
 use sp_blockchain::Error;
-use prometheus_endpoint::Registry;
+use prometheus_endpoint::{register, Counter, Gauge, PrometheusError, Registry, U64};
 use super::{
 	genesis, block_rules::{BlockRules, LookupResult as BlockLookupResult},
 };
@@ -107,6 +107,36 @@ use {
 
 type NotificationSinks<T> = Mutex<Vec<TracingUnboundedSender<T>>>;
 
+/// Prometheus metrics of the blocks imported by the client.
+///
+/// Reorgs are counted when an imported block or a finalized block on another fork becomes the
+/// best block. Heads moved directly on the backend, e.g. through the `set_head` of the light
+/// client storage, aren't seen by the client and aren't counted.
+struct ClientMetrics {
+	reorgs: Counter<U64>,
+	last_reorg_depth: Gauge<U64>,
+}
+
+impl ClientMetrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(ClientMetrics {
+			reorgs: register(Counter::new(
+				"reorgs_total",
+				"Number of times the best block moved to a different fork",
+			)?, registry)?,
+			last_reorg_depth: register(Gauge::new(
+				"last_reorg_depth",
+				"Number of blocks retracted from the best chain by the last reorg",
+			)?, registry)?,
+		})
+	}
+
+	fn report_reorg(&self, depth: usize) {
+		self.reorgs.inc();
+		self.last_reorg_depth.set(depth as u64);
+	}
+}
+
 /// Substrate Client
 pub struct Client<B, E, Block, RA> where Block: BlockT {
 	backend: Arc<B>,
@@ -120,6 +150,7 @@ pub struct Client<B, E, Block, RA> where Block: BlockT {
 	execution_extensions: ExecutionExtensions<Block>,
 	config: ClientConfig<Block>,
 	telemetry: Option<TelemetryHandle>,
+	metrics: Option<ClientMetrics>,
 	_phantom: PhantomData<RA>,
 }
 
@@ -344,6 +375,14 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			backend.commit_operation(op)?;
 		}
 
+		let metrics = prometheus_registry.as_ref().and_then(|registry| {
+			ClientMetrics::register(registry)
+				.map_err(|err| {
+					warn!("Failed to register Prometheus metrics: {}", err);
+				})
+				.ok()
+		});
+
 		Ok(Client {
 			backend,
 			executor,
@@ -355,6 +394,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			execution_extensions,
 			config,
 			telemetry,
+			metrics,
 			_phantom: Default::default(),
 		})
 	}
@@ -971,6 +1011,13 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			// an accurate "best" block need to go through `SelectChain`
 			// instead.
 			operation.op.mark_head(BlockId::Hash(block))?;
+
+			// The best block moves to another fork.
+			if let Some(metrics) = self.metrics.as_ref() {
+				if !route_from_best.retracted().is_empty() {
+					metrics.report_reorg(route_from_best.retracted().len());
+				}
+			}
 		}
 
 		let enacted = route_from_finalized.enacted();
@@ -1068,6 +1115,15 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			}
 		};
 
+		if let (Some(metrics), Some(tree_route)) =
+			(self.metrics.as_ref(), notify_import.tree_route.as_ref())
+		{
+			// A new best block on top of another fork of the current best one.
+			if !tree_route.retracted().is_empty() {
+				metrics.report_reorg(tree_route.retracted().len());
+			}
+		}
+
 		if let Some(storage_changes) = notify_import.storage_changes {
 			// TODO [ToDr] How to handle re-orgs? Should we re-emit all storage changes?
 			self.storage_notifications.lock()
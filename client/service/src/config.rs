@@ -31,12 +31,16 @@ pub use sc_network::config::{
 pub use sc_executor::WasmExecutionMethod;
 pub use sc_client_api::execution_extensions::{ExecutionStrategies, ExecutionStrategy};
 
-use std::{io, future::Future, path::{PathBuf, Path}, pin::Pin, net::SocketAddr, sync::Arc};
+use std::{
+	io, future::Future, path::{PathBuf, Path}, pin::Pin, net::SocketAddr, sync::Arc,
+	collections::{HashMap, HashSet},
+};
 pub use sc_transaction_pool::txpool::Options as TransactionPoolOptions;
 use sc_chain_spec::ChainSpec;
 use sp_core::crypto::SecretString;
 pub use sc_telemetry::TelemetryEndpoints;
-use prometheus_endpoint::Registry;
+pub use crate::metrics::MetricFamily;
+use prometheus_endpoint::{Registry, PrometheusError};
 #[cfg(not(target_os = "unknown"))]
 use tempfile::TempDir;
 
@@ -100,6 +104,8 @@ pub struct Configuration {
 	/// External WASM transport for the telemetry. If `Some`, when connection to a telemetry
 	/// endpoint, this transport will be tried in priority before all others.
 	pub telemetry_external_transport: Option<ExtTransport>,
+	/// Fields sent in the periodic `system.interval` telemetry message.
+	pub telemetry_fields: TelemetryFields,
 	/// The default number of 64KB pages to allocate for Wasm execution
 	pub default_heap_pages: Option<u64>,
 	/// Should offchain workers be executed.
@@ -173,6 +179,67 @@ pub struct OffchainWorkerConfig {
 	pub indexing_enabled: bool,
 }
 
+/// Fields sent in the periodic `system.interval` telemetry message.
+///
+/// Everything is sent by default. Nodes on a constrained link can leave some fields out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TelemetryFields {
+	/// Best block height and hash.
+	pub best: bool,
+	/// Finalized block height and hash.
+	pub finalized: bool,
+	/// Number of transactions in the ready queue.
+	pub txcount: bool,
+	/// Size of the state cache.
+	pub state_cache: bool,
+	/// Number of connected peers.
+	pub peers: bool,
+	/// Average download and upload bandwidth.
+	pub bandwidth: bool,
+}
+
+impl TelemetryFields {
+	/// Only send the number of peers and the best and finalized blocks.
+	pub fn minimal() -> Self {
+		Self {
+			best: true,
+			finalized: true,
+			txcount: false,
+			state_cache: false,
+			peers: true,
+			bandwidth: false,
+		}
+	}
+
+	/// Returns whether the given `system.interval` field should be sent.
+	///
+	/// Unknown fields are always sent.
+	pub fn contains(&self, field: &str) -> bool {
+		match field {
+			"height" | "best" => self.best,
+			"finalized_height" | "finalized_hash" => self.finalized,
+			"txcount" => self.txcount,
+			"used_state_cache_size" => self.state_cache,
+			"peers" => self.peers,
+			"bandwidth_download" | "bandwidth_upload" => self.bandwidth,
+			_ => true,
+		}
+	}
+}
+
+impl Default for TelemetryFields {
+	fn default() -> Self {
+		Self {
+			best: true,
+			finalized: true,
+			txcount: true,
+			state_cache: true,
+			peers: true,
+			bandwidth: true,
+		}
+	}
+}
+
 /// Configuration of the Prometheus endpoint.
 #[derive(Debug, Clone)]
 pub struct PrometheusConfig {
@@ -180,6 +247,16 @@ pub struct PrometheusConfig {
 	pub port: SocketAddr,
 	/// A metrics registry to use. Useful for setting the metric prefix.
 	pub registry: Registry,
+	/// Metric families the metrics service doesn't update.
+	pub disabled_metrics: HashSet<MetricFamily>,
+	/// If set, metrics are served on a Unix domain socket at this path instead of on `port`.
+	///
+	/// Only supported on Unix platforms, starting the node fails elsewhere.
+	pub unix_socket: Option<PathBuf>,
+	/// Don't serve the metrics, log the registered families and their values once instead.
+	///
+	/// Useful to check the configuration of the metrics without setting up a scraper.
+	pub dry_run: bool,
 }
 
 impl PrometheusConfig {
@@ -190,9 +267,116 @@ impl PrometheusConfig {
 		Self {
 			port,
 			registry: Registry::new_custom(Some("substrate".into()), None)
-				.expect("this can only fail if the prefix is empty")
+				.expect("this can only fail if the prefix is empty"),
+			disabled_metrics: HashSet::new(),
+			unix_socket: None,
+			dry_run: false,
 		}
 	}
+
+	/// Create a new config using a registry that prefixes metrics with `prefix`.
+	///
+	/// The prefix ends up at the start of every metric name, so it has to be a valid Prometheus
+	/// metric name itself, i.e. match `[a-zA-Z_:][a-zA-Z0-9_:]*`.
+	pub fn new_with_prefix(port: SocketAddr, prefix: &str) -> Result<Self, PrometheusError> {
+		Self::new_with_const_labels(port, prefix, HashMap::new())
+	}
+
+	/// Create a new config using a registry that prefixes metrics with `prefix` and labels all
+	/// of them with `const_labels`, e.g. to tell apart the regions or instances of a fleet.
+	///
+	/// The label names have to match `[a-zA-Z_][a-zA-Z0-9_]*` and may not start with `__`,
+	/// which is reserved for Prometheus' internal use. The names of the labels set by the node's
+	/// own metrics, e.g. `status` or `version`, are rejected as well, see
+	/// [`RESERVED_LABEL_NAMES`].
+	pub fn new_with_const_labels(
+		port: SocketAddr,
+		prefix: &str,
+		const_labels: HashMap<String, String>,
+	) -> Result<Self, PrometheusError> {
+		if !is_valid_metric_name(prefix) {
+			return Err(PrometheusError::Msg(format!(
+				"Invalid metrics prefix {:?}: only ASCII letters, digits, '_' and ':' are allowed \
+				and it may not start with a digit",
+				prefix,
+			)))
+		}
+
+		if let Some(name) = const_labels.keys().find(|name| !is_valid_label_name(name)) {
+			return Err(PrometheusError::Msg(format!(
+				"Invalid metrics label name {:?}: only ASCII letters, digits and '_' are allowed, \
+				it may not start with a digit or with '__'",
+				name,
+			)))
+		}
+
+		let reserved = const_labels.keys()
+			.find(|name| RESERVED_LABEL_NAMES.contains(&name.as_str()));
+		if let Some(name) = reserved {
+			return Err(PrometheusError::Msg(format!(
+				"Metrics label name {:?} is already used by the metrics of the node",
+				name,
+			)))
+		}
+
+		let const_labels = if const_labels.is_empty() { None } else { Some(const_labels) };
+		Ok(Self {
+			port,
+			registry: Registry::new_custom(Some(prefix.into()), const_labels)?,
+			disabled_metrics: HashSet::new(),
+			unix_socket: None,
+			dry_run: false,
+		})
+	}
+}
+
+/// Label names set by the metrics of the node, or by Prometheus for histograms and summaries.
+///
+/// A constant label with one of these names would collide with them.
+pub const RESERVED_LABEL_NAMES: &[&str] = &[
+	"action",
+	"backend",
+	"direction",
+	"entity",
+	"event_name",
+	"genesis_hash",
+	"impl_version",
+	"le",
+	"message",
+	"method",
+	"name",
+	"protocol",
+	"quantile",
+	"queue",
+	"reason",
+	"result",
+	"spec_version",
+	"status",
+	"subsystem",
+	"subtype",
+	"task_name",
+	"version",
+];
+
+/// Returns whether `name` matches the Prometheus metric name format `[a-zA-Z_:][a-zA-Z0-9_:]*`.
+fn is_valid_metric_name(name: &str) -> bool {
+	is_valid_name(name, |c| c == ':')
+}
+
+/// Returns whether `name` matches the Prometheus label name format `[a-zA-Z_][a-zA-Z0-9_]*` and
+/// isn't reserved.
+fn is_valid_label_name(name: &str) -> bool {
+	!name.starts_with("__") && is_valid_name(name, |_| false)
+}
+
+fn is_valid_name(name: &str, extra_char: impl Fn(char) -> bool) -> bool {
+	let mut chars = name.chars();
+	match chars.next() {
+		Some(c) if c.is_ascii_alphabetic() || c == '_' || extra_char(c) => (),
+		_ => return false,
+	}
+
+	chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || extra_char(c))
 }
 
 impl Configuration {
@@ -361,3 +545,72 @@ impl TaskExecutor {
 		self.0(future, task_type)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn metrics_prefix_is_validated() {
+		let port = SocketAddr::from(([127, 0, 0, 1], 9615));
+
+		assert!(PrometheusConfig::new_with_prefix(port, "polkadot").is_ok());
+		assert!(PrometheusConfig::new_with_prefix(port, "my_chain:node").is_ok());
+
+		assert!(PrometheusConfig::new_with_prefix(port, "my-chain").is_err());
+		assert!(PrometheusConfig::new_with_prefix(port, "1chain").is_err());
+		assert!(PrometheusConfig::new_with_prefix(port, "").is_err());
+	}
+
+	#[test]
+	fn const_labels_are_applied_to_all_metrics() {
+		let port = SocketAddr::from(([127, 0, 0, 1], 9615));
+		let labels = |pairs: &[(&str, &str)]| pairs.iter()
+			.map(|(name, value)| (name.to_string(), value.to_string()))
+			.collect::<HashMap<_, _>>();
+
+		let config = PrometheusConfig::new_with_const_labels(
+			port,
+			"substrate",
+			labels(&[("region", "eu-west"), ("instance", "validator-1")]),
+		).unwrap();
+		prometheus_endpoint::register(
+			prometheus_endpoint::Gauge::<prometheus_endpoint::U64>::new("test_gauge", "Test")
+				.unwrap(),
+			&config.registry,
+		).unwrap().set(1);
+
+		let families = config.registry.gather();
+		let metric = &families[0].get_metric()[0];
+		let mut found = metric.get_label().iter()
+			.map(|pair| (pair.get_name(), pair.get_value()))
+			.collect::<Vec<_>>();
+		found.sort();
+		assert_eq!(families[0].get_name(), "substrate_test_gauge");
+		assert_eq!(found, vec![("instance", "validator-1"), ("region", "eu-west")]);
+
+		for invalid in &["my-label", "1label", "__reserved", ""] {
+			let result = PrometheusConfig::new_with_const_labels(
+				port,
+				"substrate",
+				labels(&[(*invalid, "value")]),
+			);
+			assert!(result.is_err(), "{:?} should be rejected", invalid);
+		}
+	}
+
+	#[test]
+	fn const_labels_may_not_collide_with_the_node_labels() {
+		let port = SocketAddr::from(([127, 0, 0, 1], 9615));
+		let config = |name: &str| PrometheusConfig::new_with_const_labels(
+			port,
+			"substrate",
+			vec![(name.to_string(), "value".to_string())].into_iter().collect(),
+		);
+
+		for reserved in &["status", "version", "genesis_hash", "subsystem", "method", "le"] {
+			assert!(config(reserved).is_err(), "{:?} should be rejected", reserved);
+		}
+		assert!(config("region").is_ok());
+	}
+}
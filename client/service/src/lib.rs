@@ -33,6 +33,8 @@ pub mod client;
 #[cfg(not(feature = "test-helpers"))]
 mod client;
 mod task_manager;
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod testing;
 
 use std::{io, pin::Pin};
 use std::net::SocketAddr;
@@ -51,7 +53,7 @@ use sp_utils::mpsc::TracingUnboundedReceiver;
 pub use self::error::Error;
 pub use self::builder::{
 	new_full_client, new_db_backend, new_client, new_full_parts, new_light_parts,
-	spawn_tasks, build_network, build_offchain_workers,
+	spawn_tasks, spawn_tasks_with_metrics, build_network, build_offchain_workers,
 	BuildNetworkParams, KeystoreContainer, NetworkStarter, SpawnTasksParams, TFullClient, TLightClient,
 	TFullBackend, TLightBackend, TLightBackendWithHash, TLightClientWithBackend,
 	TFullCallExecutor, TLightCallExecutor, RpcExtensionBuilder, NoopRpcExtensionBuilder,
@@ -76,6 +78,10 @@ pub use sc_network::config::{
 	TransactionImportFuture,
 };
 pub use sc_tracing::TracingReceiver;
+pub use metrics::{
+	MemoryProvider, MetricDescription, MetricFamily, MetricsHandle, MetricsService,
+	MetricsSnapshot, Pause, TickInterval,
+};
 pub use task_manager::SpawnTaskHandle;
 pub use task_manager::TaskManager;
 pub use sp_consensus::import_queue::ImportQueue;
@@ -398,6 +404,7 @@ fn start_rpc_servers<
 					deny_unsafe(&address, &config.rpc_methods),
 					sc_rpc_server::RpcMiddleware::new(rpc_metrics.clone(), "ws")
 				),
+				rpc_metrics.clone(),
 			),
 		)?.map(|s| waiting::WsServer(Some(s))),
 	)))
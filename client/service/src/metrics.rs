@@ -16,90 +16,782 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::{convert::TryFrom, time::SystemTime};
+use std::{
+	collections::{HashSet, VecDeque}, convert::TryFrom, fmt, panic::{self, AssertUnwindSafe},
+	str::FromStr, sync::atomic::{AtomicBool, AtomicU64, Ordering}, time::SystemTime,
+};
 
-use crate::config::Configuration;
+use crate::config::{Configuration, TelemetryFields};
+use futures::{channel::mpsc, Stream};
 use futures_timer::Delay;
-use prometheus_endpoint::{register, Gauge, U64, Registry, PrometheusError, Opts, GaugeVec};
+use prometheus_endpoint::{
+	register, Gauge, U64, F64, Registry, PrometheusError, Opts, GaugeVec,
+	prometheus::{Encoder, TextEncoder, core::{Atomic, Collector, Number}},
+};
 use sc_telemetry::{telemetry, TelemetryHandle, SUBSTRATE_INFO};
-use sp_api::ProvideRuntimeApi;
-use sp_runtime::traits::{NumberFor, Block, SaturatedConversion, UniqueSaturatedInto};
+use sp_api::{CallApiAt, ProvideRuntimeApi};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{NumberFor, Block, SaturatedConversion, UniqueSaturatedInto},
+};
 use sp_transaction_pool::{PoolStatus, MaintainedTransactionPool};
-use sp_utils::metrics::register_globals;
-use sc_client_api::{ClientInfo, UsageProvider};
+use sp_utils::metrics::{register_globals, unbounded_channels_depth};
+use sc_client_api::{BlockBackend, ClientInfo, UsageProvider};
 use sc_network::{config::Role, NetworkStatus, NetworkService, network_state::NetworkState};
 use std::sync::Arc;
 use std::time::Duration;
+use parking_lot::{Mutex, RwLock};
 use wasm_timer::Instant;
+use serde::Serialize;
+
+/// Static information about the node, reported once when the metrics are registered.
+#[derive(Clone)]
+struct NodeInfo {
+	name: String,
+	version: String,
+	roles: u64,
+	database: String,
+	genesis_hash: String,
+	/// Time between the UNIX epoch and the moment the process started.
+	start_time: Duration,
+}
+
+impl NodeInfo {
+	fn new(config: &Configuration, genesis_hash: &str) -> Self {
+		let roles = match config.role {
+			Role::Full => 1u64,
+			Role::Light => 2u64,
+			Role::Authority { .. } => 4u64,
+		};
+
+		NodeInfo {
+			name: config.network.node_name.clone(),
+			version: config.impl_version.clone(),
+			roles,
+			database: config.database.to_string().to_lowercase(),
+			genesis_hash: genesis_hash.into(),
+			start_time: SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)
+				.unwrap_or_default(),
+		}
+	}
+}
 
 struct PrometheusMetrics {
+	node: NodeInfo,
+	registry: Registry,
+	/// Metrics with a constant value, set once when registering.
+	info: Vec<Box<dyn Collector>>,
+
 	// generic info
 	block_height: GaugeVec<U64>,
+	finality_stall: Gauge<U64>,
+	sync_eta: Gauge<F64>,
 	number_leaves: Gauge<U64>,
+	runtime_version: GaugeVec<U64>,
+	finalized_block_extrinsics: Gauge<U64>,
 	ready_transactions_number: Gauge<U64>,
+	txpool_bytes: GaugeVec<U64>,
 
 	// I/O
 	database_cache: Gauge<U64>,
 	state_cache: Gauge<U64>,
 	state_db: GaugeVec<U64>,
+	state_db_pinned: Gauge<U64>,
+	subsystem_memory: GaugeVec<U64>,
+
+	// internals
+	paused: Gauge<U64>,
+	last_update: Gauge<U64>,
+	tick_interval: Gauge<F64>,
+	tick_drift: Gauge<F64>,
+	max_unbounded_channel_depth: Gauge<U64>,
+	max_unbounded_channel: GaugeVec<U64>,
 }
 
 impl PrometheusMetrics {
-	fn setup(
+	/// Registers the metrics in `registry`.
+	///
+	/// If registering fails, the metrics registered until then are unregistered again.
+	fn setup(registry: &Registry, node: NodeInfo) -> Result<Self, PrometheusError> {
+		let mut registered = Vec::new();
+		let result = Self::register_all(registry, node, &mut registered);
+		if result.is_err() {
+			for metric in registered {
+				let _ = registry.unregister(metric);
+			}
+		}
+		result
+	}
+
+	fn register_all(
 		registry: &Registry,
-		name: &str,
-		version: &str,
-		roles: u64,
+		node: NodeInfo,
+		registered: &mut Vec<Box<dyn Collector>>,
 	) -> Result<Self, PrometheusError> {
-		register(Gauge::<U64>::with_opts(
+		let build_info = register_in(Gauge::<U64>::with_opts(
 			Opts::new(
 				"build_info",
 				"A metric with a constant '1' value labeled by name, version"
 			)
-				.const_label("name", name)
-				.const_label("version", version)
-		)?, &registry)?.set(1);
+				.const_label("name", &node.name)
+				.const_label("version", &node.version)
+		)?, registry, registered)?;
+		build_info.set(1);
 
-		register(Gauge::<U64>::new(
+		let node_roles = register_in(Gauge::<U64>::new(
 			"node_roles", "The roles the node is running as",
-		)?, &registry)?.set(roles);
+		)?, registry, registered)?;
+		node_roles.set(node.roles);
 
-		register_globals(registry)?;
+		let genesis_info = register_in(Gauge::<U64>::with_opts(
+			Opts::new(
+				"chain_genesis_info",
+				"A metric with a constant '1' value labeled by the genesis hash of the chain"
+			)
+				.const_label("genesis_hash", &node.genesis_hash)
+		)?, registry, registered)?;
+		genesis_info.set(1);
 
-		let start_time_since_epoch = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)
-			.unwrap_or_default();
-		register(Gauge::<U64>::new(
+		let start_time = register_in(Gauge::<U64>::new(
 			"process_start_time_seconds",
 			"Number of seconds between the UNIX epoch and the moment the process started",
-		)?, registry)?.set(start_time_since_epoch.as_secs());
+		)?, registry, registered)?;
+		start_time.set(node.start_time.as_secs());
+
+		let metrics = Self {
+			info: vec![
+				Box::new(build_info),
+				Box::new(node_roles),
+				Box::new(genesis_info),
+				Box::new(start_time),
+			],
 
-		Ok(Self {
 			// generic internals
-			block_height: register(GaugeVec::new(
+			block_height: register_in(GaugeVec::new(
 				Opts::new("block_height", "Block height info of the chain"),
 				&["status"]
-			)?, registry)?,
+			)?, registry, registered)?,
 
-			number_leaves: register(Gauge::new(
+			finality_stall: register_in(Gauge::new(
+				"seconds_since_finality_advance",
+				"Number of seconds since the finalized block number last increased",
+			)?, registry, registered)?,
+
+			sync_eta: register_in(Gauge::new(
+				"sync_eta_seconds",
+				"Estimated number of seconds until the sync target is reached, +Inf when no blocks \
+				were imported recently, only updated while the sync target is ahead",
+			)?, registry, registered)?,
+
+			number_leaves: register_in(Gauge::new(
 				"number_leaves", "Number of known chain leaves (aka forks)",
-			)?, registry)?,
+			)?, registry, registered)?,
+
+			runtime_version: register_in(GaugeVec::new(
+				Opts::new(
+					"runtime_version_info",
+					"A metric with a constant '1' value labeled by the version of the runtime at \
+					the best block",
+				),
+				&["spec_version", "impl_version"]
+			)?, registry, registered)?,
+
+			finalized_block_extrinsics: register_in(Gauge::new(
+				"finalized_block_extrinsics",
+				"Number of extrinsics in the last finalized block",
+			)?, registry, registered)?,
 
-			ready_transactions_number: register(Gauge::new(
+			ready_transactions_number: register_in(Gauge::new(
 				"ready_transactions_number", "Number of transactions in the ready queue",
-			)?, registry)?,
+			)?, registry, registered)?,
+
+			txpool_bytes: register_in(GaugeVec::new(
+				Opts::new("txpool_bytes", "Size of the transactions in the pool, in bytes"),
+				&["queue"]
+			)?, registry, registered)?,
 
 			// I/ O
-			database_cache: register(Gauge::new(
-				"database_cache_bytes", "RocksDB cache size in bytes",
-			)?, registry)?,
-			state_cache: register(Gauge::new(
+			database_cache: register_in(Gauge::with_opts(
+				Opts::new("database_cache_bytes", "Database cache size in bytes")
+					.const_label("backend", &node.database)
+			)?, registry, registered)?,
+			state_cache: register_in(Gauge::new(
 				"state_cache_bytes", "State cache size in bytes",
-			)?, registry)?,
-			state_db: register(GaugeVec::new(
+			)?, registry, registered)?,
+			state_db: register_in(GaugeVec::new(
 				Opts::new("state_db_cache_bytes", "State DB cache in bytes"),
 				&["subtype"]
-			)?, registry)?,
-		})
+			)?, registry, registered)?,
+			state_db_pinned: register_in(Gauge::new(
+				"state_db_pinned_count", "Number of blocks pinned in the state DB",
+			)?, registry, registered)?,
+			subsystem_memory: register_in(GaugeVec::new(
+				Opts::new("subsystem_memory_bytes", "Memory used by a subsystem, in bytes"),
+				&["subsystem"]
+			)?, registry, registered)?,
+
+			// internals
+			paused: register_in(Gauge::new(
+				"metrics_paused", "Whether the updates of these metrics are paused, 0 or 1",
+			)?, registry, registered)?,
+			last_update: register_in(Gauge::new(
+				"metrics_last_tick_unixtime",
+				"Number of seconds between the UNIX epoch and the last update of these metrics",
+			)?, registry, registered)?,
+			tick_interval: register_in(Gauge::new(
+				"metrics_tick_interval_seconds",
+				"Intended interval between updates of these metrics",
+			)?, registry, registered)?,
+			tick_drift: register_in(Gauge::new(
+				"metrics_tick_drift_seconds",
+				"Observed minus intended interval between updates of these metrics, positive when \
+				they fall behind",
+			)?, registry, registered)?,
+			max_unbounded_channel_depth: register_in(Gauge::new(
+				"max_unbounded_channel_depth",
+				"Number of items queued in the unbounded channel with the largest backlog",
+			)?, registry, registered)?,
+			max_unbounded_channel: register_in(GaugeVec::new(
+				Opts::new(
+					"max_unbounded_channel",
+					"A metric with a constant '1' value labeled by the unbounded channel with the \
+					largest backlog",
+				),
+				&["entity"]
+			)?, registry, registered)?,
+
+			node,
+			registry: registry.clone(),
+		};
+
+		// Registered last, as they can't be unregistered if registering anything else fails.
+		register_globals(registry)?;
+
+		Ok(metrics)
+	}
+
+	/// Returns the metric of `family`.
+	fn family(&self, family: MetricFamily) -> &dyn FamilyMetric {
+		match family {
+			MetricFamily::BlockHeight => &self.block_height,
+			MetricFamily::FinalityStall => &self.finality_stall,
+			MetricFamily::SyncEta => &self.sync_eta,
+			MetricFamily::NumberLeaves => &self.number_leaves,
+			MetricFamily::RuntimeVersion => &self.runtime_version,
+			MetricFamily::FinalizedBlockExtrinsics => &self.finalized_block_extrinsics,
+			MetricFamily::ReadyTransactions => &self.ready_transactions_number,
+			MetricFamily::TxpoolBytes => &self.txpool_bytes,
+			MetricFamily::DatabaseCache => &self.database_cache,
+			MetricFamily::StateCache => &self.state_cache,
+			MetricFamily::StateDb => &self.state_db,
+			MetricFamily::StateDbPinned => &self.state_db_pinned,
+			MetricFamily::SubsystemMemory => &self.subsystem_memory,
+			MetricFamily::Paused => &self.paused,
+			MetricFamily::LastUpdate => &self.last_update,
+			MetricFamily::TickInterval => &self.tick_interval,
+			MetricFamily::TickDrift => &self.tick_drift,
+			MetricFamily::MaxUnboundedChannelDepth => &self.max_unbounded_channel_depth,
+			MetricFamily::MaxUnboundedChannel => &self.max_unbounded_channel,
+		}
+	}
+
+	/// Reports the channel with the largest backlog, if any, as returned by [`largest_channel`].
+	fn report_largest_channel(
+		&self,
+		largest: Option<(String, u64)>,
+		report_depth: bool,
+		report_channel: bool,
+	) {
+		if report_depth {
+			self.max_unbounded_channel_depth.set(largest.as_ref().map_or(0, |(_, depth)| *depth));
+		}
+		if report_channel {
+			self.max_unbounded_channel.reset();
+			if let Some((entity, _)) = largest {
+				self.max_unbounded_channel.with_label_values(&[&entity]).set(1);
+			}
+		}
+	}
+}
+
+/// The metric of a [`MetricFamily`], as far as disabling the family is concerned.
+trait FamilyMetric {
+	/// Returns the metric, to register or unregister it.
+	fn collector(&self) -> Box<dyn Collector>;
+
+	/// Clears the series of the metric.
+	fn clear(&self);
+}
+
+impl<P: Atomic + 'static> FamilyMetric for Gauge<P> {
+	fn collector(&self) -> Box<dyn Collector> {
+		Box::new(self.clone())
+	}
+
+	fn clear(&self) {
+		self.set(P::T::from_i64(0));
+	}
+}
+
+impl<P: Atomic + 'static> FamilyMetric for GaugeVec<P> {
+	fn collector(&self) -> Box<dyn Collector> {
+		Box::new(self.clone())
+	}
+
+	fn clear(&self) {
+		self.reset();
+	}
+}
+
+/// Registers `metric` in `registry` and remembers it in `registered`.
+fn register_in<T: Clone + Collector + 'static>(
+	metric: T,
+	registry: &Registry,
+	registered: &mut Vec<Box<dyn Collector>>,
+) -> Result<T, PrometheusError> {
+	let metric = register(metric, registry)?;
+	registered.push(Box::new(metric.clone()));
+	Ok(metric)
+}
+
+/// A metric family updated periodically by the [`MetricsService`], which can be disabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetricFamily {
+	/// `block_height`
+	BlockHeight,
+	/// `seconds_since_finality_advance`
+	FinalityStall,
+	/// `sync_eta_seconds`
+	SyncEta,
+	/// `number_leaves`
+	NumberLeaves,
+	/// `runtime_version_info`
+	RuntimeVersion,
+	/// `finalized_block_extrinsics`
+	FinalizedBlockExtrinsics,
+	/// `ready_transactions_number`
+	ReadyTransactions,
+	/// `txpool_bytes`
+	TxpoolBytes,
+	/// `database_cache_bytes`
+	DatabaseCache,
+	/// `state_cache_bytes`
+	StateCache,
+	/// `state_db_cache_bytes`
+	StateDb,
+	/// `state_db_pinned_count`
+	StateDbPinned,
+	/// `subsystem_memory_bytes`
+	SubsystemMemory,
+	/// `metrics_paused`
+	Paused,
+	/// `metrics_last_tick_unixtime`
+	LastUpdate,
+	/// `metrics_tick_interval_seconds`
+	TickInterval,
+	/// `metrics_tick_drift_seconds`
+	TickDrift,
+	/// `max_unbounded_channel_depth`
+	MaxUnboundedChannelDepth,
+	/// `max_unbounded_channel`
+	MaxUnboundedChannel,
+}
+
+impl MetricFamily {
+	/// All the families.
+	pub const ALL: [MetricFamily; 19] = [
+		MetricFamily::BlockHeight,
+		MetricFamily::FinalityStall,
+		MetricFamily::SyncEta,
+		MetricFamily::NumberLeaves,
+		MetricFamily::RuntimeVersion,
+		MetricFamily::FinalizedBlockExtrinsics,
+		MetricFamily::ReadyTransactions,
+		MetricFamily::TxpoolBytes,
+		MetricFamily::DatabaseCache,
+		MetricFamily::StateCache,
+		MetricFamily::StateDb,
+		MetricFamily::StateDbPinned,
+		MetricFamily::SubsystemMemory,
+		MetricFamily::Paused,
+		MetricFamily::LastUpdate,
+		MetricFamily::TickInterval,
+		MetricFamily::TickDrift,
+		MetricFamily::MaxUnboundedChannelDepth,
+		MetricFamily::MaxUnboundedChannel,
+	];
+
+	/// Name of the family, without the registry prefix.
+	pub fn name(self) -> &'static str {
+		match self {
+			MetricFamily::BlockHeight => "block_height",
+			MetricFamily::FinalityStall => "seconds_since_finality_advance",
+			MetricFamily::SyncEta => "sync_eta_seconds",
+			MetricFamily::NumberLeaves => "number_leaves",
+			MetricFamily::RuntimeVersion => "runtime_version_info",
+			MetricFamily::FinalizedBlockExtrinsics => "finalized_block_extrinsics",
+			MetricFamily::ReadyTransactions => "ready_transactions_number",
+			MetricFamily::TxpoolBytes => "txpool_bytes",
+			MetricFamily::DatabaseCache => "database_cache_bytes",
+			MetricFamily::StateCache => "state_cache_bytes",
+			MetricFamily::StateDb => "state_db_cache_bytes",
+			MetricFamily::StateDbPinned => "state_db_pinned_count",
+			MetricFamily::SubsystemMemory => "subsystem_memory_bytes",
+			MetricFamily::Paused => "metrics_paused",
+			MetricFamily::LastUpdate => "metrics_last_tick_unixtime",
+			MetricFamily::TickInterval => "metrics_tick_interval_seconds",
+			MetricFamily::TickDrift => "metrics_tick_drift_seconds",
+			MetricFamily::MaxUnboundedChannelDepth => "max_unbounded_channel_depth",
+			MetricFamily::MaxUnboundedChannel => "max_unbounded_channel",
+		}
+	}
+}
+
+impl fmt::Display for MetricFamily {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
+impl FromStr for MetricFamily {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		MetricFamily::ALL.iter().copied()
+			.find(|family| family.name() == name)
+			.ok_or_else(|| format!("Unknown or not updated metric family {}", name))
+	}
+}
+
+/// Name, help text and label names of a registered metric family.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricDescription {
+	/// Name of the family, without the registry prefix.
+	pub name: String,
+	/// Help text of the family.
+	pub help: String,
+	/// Names of the labels of the family, including constant labels.
+	pub labels: Vec<String>,
+}
+
+/// Estimates the number of bytes of memory used by a subsystem.
+pub type MemoryProvider = Box<dyn Fn() -> u64 + Send>;
+
+/// Default interval between two updates of a [`MetricsService`].
+const DEFAULT_TICK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Number of snapshots buffered for a subscriber before newer ones are dropped.
+const SUBSCRIBER_BUFFER: usize = 16;
+
+/// Handle to the interval between two updates of a [`MetricsService`].
+///
+/// It can be used to change the interval while the service is running.
+#[derive(Clone)]
+pub struct TickInterval(Arc<AtomicU64>);
+
+impl TickInterval {
+	fn new(interval: Duration) -> Self {
+		let handle = TickInterval(Arc::new(AtomicU64::new(0)));
+		handle.set(interval);
+		handle
+	}
+
+	/// Returns the current interval.
+	pub fn get(&self) -> Duration {
+		Duration::from_millis(self.0.load(Ordering::Relaxed))
+	}
+
+	/// Changes the interval, taking effect after the next update.
+	///
+	/// The interval is rounded down to milliseconds, and to at least one millisecond.
+	pub fn set(&self, interval: Duration) {
+		let millis = u64::try_from(interval.as_millis()).unwrap_or(u64::MAX);
+		self.0.store(millis.max(1), Ordering::Relaxed);
+	}
+}
+
+/// Handle to pause and resume the updates of a [`MetricsService`].
+///
+/// It can be used to pause the updates while the service is running.
+#[derive(Clone, Default)]
+pub struct Pause(Arc<AtomicBool>);
+
+impl Pause {
+	/// Stops updating the metrics from the next update on, until resumed.
+	///
+	/// The metrics keep their last value, only `metrics_paused` is still updated.
+	pub fn pause(&self) {
+		self.0.store(true, Ordering::Relaxed);
+	}
+
+	/// Resumes updating the metrics from the next update on.
+	pub fn resume(&self) {
+		self.0.store(false, Ordering::Relaxed);
+	}
+
+	/// Returns whether the updates are paused.
+	pub fn is_paused(&self) -> bool {
+		self.0.load(Ordering::Relaxed)
+	}
+}
+
+impl Default for TickInterval {
+	fn default() -> Self {
+		TickInterval::new(DEFAULT_TICK_INTERVAL)
+	}
+}
+
+/// Values reported by the last update of a [`MetricsService`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsSnapshot {
+	/// Best block number.
+	pub best_number: u64,
+	/// Finalized block number.
+	pub finalized_number: u64,
+	/// Best block number seen among the peers, if known.
+	///
+	/// This isn't monotonic: it drops when the peer that announced the highest block disconnects.
+	pub sync_target: Option<u64>,
+	/// Number of known chain leaves.
+	pub number_leaves: u64,
+	/// Number of transactions in the ready queue.
+	pub ready_transactions: u64,
+	/// Number of connected peers, if the network status is known.
+	pub peers: Option<u64>,
+	/// Average download bandwidth in bytes per second, if the network status is known.
+	pub bandwidth_download: Option<u64>,
+	/// Average upload bandwidth in bytes per second, if the network status is known.
+	pub bandwidth_upload: Option<u64>,
+	/// Size of the state cache in bytes, if the backend reports it.
+	pub state_cache_bytes: Option<u64>,
+	/// Size of the database cache in bytes, if the backend reports it.
+	pub database_cache_bytes: Option<u64>,
+}
+
+/// Prometheus metrics of a [`MetricsService`] and what was reported to them.
+#[derive(Default)]
+struct PrometheusState {
+	metrics: Option<PrometheusMetrics>,
+	disabled_families: HashSet<MetricFamily>,
+	/// Spec and implementation version of the runtime last reported.
+	runtime_version: Option<(u32, u32)>,
+	/// Whether the number of extrinsics of the finalized block was reported since the gauge was
+	/// last reset.
+	finalized_body_reported: bool,
+}
+
+impl PrometheusState {
+	/// Returns the metrics, failing if Prometheus isn't enabled.
+	fn enabled_metrics(&self) -> Result<&PrometheusMetrics, PrometheusError> {
+		self.metrics.as_ref()
+			.ok_or_else(|| PrometheusError::Msg("Prometheus metrics are not enabled".into()))
+	}
+
+	/// Returns the metrics if `family` is reported to, i.e. Prometheus is enabled and the family
+	/// isn't disabled.
+	fn reporting(&self, family: MetricFamily) -> Option<&PrometheusMetrics> {
+		self.metrics.as_ref().filter(|_| !self.disabled_families.contains(&family))
+	}
+}
+
+/// Handle to a [`MetricsService`], that stays usable once the service runs.
+#[derive(Clone, Default)]
+pub struct MetricsHandle {
+	tick_interval: TickInterval,
+	pause: Pause,
+	snapshot: Arc<RwLock<MetricsSnapshot>>,
+	subscribers: Arc<Mutex<Vec<mpsc::Sender<MetricsSnapshot>>>>,
+	prometheus: Arc<Mutex<PrometheusState>>,
+	memory_providers: Arc<Mutex<Vec<(String, MemoryProvider)>>>,
+}
+
+impl MetricsHandle {
+	/// Returns a handle to change the interval between two updates.
+	pub fn tick_interval(&self) -> TickInterval {
+		self.tick_interval.clone()
+	}
+
+	/// Returns a handle to pause and resume the updates.
+	pub fn pause_handle(&self) -> Pause {
+		self.pause.clone()
+	}
+
+	/// Returns the values reported by the last update.
+	pub fn snapshot(&self) -> MetricsSnapshot {
+		self.snapshot.read().clone()
+	}
+
+	/// Returns the values reported by the last update as JSON.
+	///
+	/// This doesn't depend on Prometheus being enabled.
+	pub fn metrics_json(&self) -> serde_json::Value {
+		serde_json::to_value(&*self.snapshot.read())
+			.expect("`MetricsSnapshot` only contains numbers, serializing it can't fail; qed")
+	}
+
+	/// Returns a stream yielding the values reported by every update from now on.
+	///
+	/// Updates don't wait for slow subscribers: once a subscriber has fallen behind by a few
+	/// snapshots, newer ones are dropped until it catches up.
+	pub fn subscribe(&self) -> impl Stream<Item = MetricsSnapshot> {
+		let (sink, stream) = mpsc::channel(SUBSCRIBER_BUFFER);
+		self.subscribers.lock().push(sink);
+		stream
+	}
+
+	/// Stops updating `family` from the next update on.
+	///
+	/// The family is unregistered, so it isn't scraped with stale values anymore.
+	///
+	/// Fails if the service was created without Prometheus metrics.
+	pub fn disable_family(&self, family: MetricFamily) -> Result<(), PrometheusError> {
+		let mut prometheus = self.prometheus.lock();
+		let metrics = prometheus.enabled_metrics()?;
+		if prometheus.disabled_families.contains(&family) {
+			return Ok(())
+		}
+
+		metrics.registry.unregister(metrics.family(family).collector())?;
+		if family == MetricFamily::RuntimeVersion {
+			prometheus.runtime_version = None;
+		}
+		prometheus.disabled_families.insert(family);
+		Ok(())
+	}
+
+	/// Resumes updating `family` from the next update on.
+	///
+	/// The family is registered again, without the values it had when it was disabled.
+	pub fn enable_family(&self, family: MetricFamily) -> Result<(), PrometheusError> {
+		let mut prometheus = self.prometheus.lock();
+		let metrics = prometheus.enabled_metrics()?;
+		if !prometheus.disabled_families.contains(&family) {
+			return Ok(())
+		}
+
+		let metric = metrics.family(family);
+		metric.clear();
+		metrics.registry.register(metric.collector())?;
+		if family == MetricFamily::FinalizedBlockExtrinsics {
+			prometheus.finalized_body_reported = false;
+		}
+		prometheus.disabled_families.remove(&family);
+		Ok(())
+	}
+
+	/// Reports the memory estimated by `provider` as used by `subsystem` on every update.
+	///
+	/// A provider that panics is logged and not called anymore.
+	pub fn register_memory_provider(&self, subsystem: impl Into<String>, provider: MemoryProvider) {
+		self.memory_providers.lock().push((subsystem.into(), provider));
+	}
+
+	/// Reports the heap memory used by `subsystem` on every update, as measured by its
+	/// `MallocSizeOf` implementation.
+	#[cfg(not(target_os = "unknown"))]
+	pub fn register_malloc_size_of<T>(&self, subsystem: impl Into<String>, subsystem_state: Arc<T>)
+	where
+		T: parity_util_mem::MallocSizeOf + Send + Sync + 'static,
+	{
+		self.register_memory_provider(
+			subsystem,
+			Box::new(move || parity_util_mem::malloc_size(&*subsystem_state) as u64),
+		);
+	}
+
+	/// Registers the Prometheus metrics in `registry` and reports to it from the next update on.
+	///
+	/// The metrics are left registered in the previous registry, they just stop being updated.
+	/// The Prometheus endpoint keeps serving the registry it was started with.
+	///
+	/// Fails if the service was created without Prometheus metrics. If registering fails,
+	/// nothing is left registered in `registry` and the metrics keep being reported to the
+	/// previous registry.
+	pub fn rebind(&self, registry: &Registry) -> Result<(), PrometheusError> {
+		let mut prometheus = self.prometheus.lock();
+		let node = prometheus.enabled_metrics()?.node.clone();
+
+		let metrics = PrometheusMetrics::setup(registry, node)?;
+		for family in &prometheus.disabled_families {
+			let _ = registry.unregister(metrics.family(*family).collector());
+		}
+		prometheus.metrics = Some(metrics);
+		// The new registry doesn't have the runtime version series yet.
+		prometheus.runtime_version = None;
+		prometheus.finalized_body_reported = false;
+		Ok(())
+	}
+
+	/// Describes the metric families registered by the service, sorted by name.
+	///
+	/// This includes families that don't have any series yet, but not the disabled ones. Names
+	/// don't include the prefix of the registry, and labels don't include its constant labels.
+	/// Returns an empty list if Prometheus isn't enabled.
+	pub fn describe(&self) -> Vec<MetricDescription> {
+		let prometheus = self.prometheus.lock();
+		let metrics = match prometheus.metrics.as_ref() {
+			Some(metrics) => metrics,
+			None => return Vec::new(),
+		};
+
+		let families = MetricFamily::ALL.iter()
+			.filter(|family| !prometheus.disabled_families.contains(family))
+			.map(|family| metrics.family(*family).collector());
+		let globals = sp_utils::metrics::globals();
+		let mut descriptions: Vec<_> = metrics.info.iter()
+			.chain(globals.iter())
+			.flat_map(|collector| describe_collector(&**collector))
+			.chain(families.flat_map(|collector| describe_collector(&*collector)))
+			.collect();
+		descriptions.sort_by(|a, b| a.name.cmp(&b.name));
+		descriptions
+	}
+
+	/// Logs the metric families registered by the service and the current value of their
+	/// series once.
+	///
+	/// Useful to check what a scraper would see without setting one up.
+	pub fn log_values(&self) {
+		if self.prometheus.lock().metrics.is_none() {
+			return
+		}
+
+		for family in self.describe() {
+			log::info!("📊 {} [{}]: {}", family.name, family.labels.join(", "), family.help);
+		}
+		match self.render_prometheus_text() {
+			Ok(text) => text.lines()
+				.filter(|line| !line.starts_with('#'))
+				.for_each(|line| log::info!("📊 {}", line)),
+			Err(err) => log::warn!("Failed to encode Prometheus metrics: {}", err),
+		}
+	}
+
+	/// Renders the current metric families of the Prometheus registry in the text exposition
+	/// format, i.e. what the Prometheus endpoint would serve.
+	///
+	/// Fails if the service was created without Prometheus metrics.
+	pub fn render_prometheus_text(&self) -> Result<String, PrometheusError> {
+		let registry = self.prometheus.lock().enabled_metrics()?.registry.clone();
+		render_text(&registry)
+	}
+
+	/// Returns the families that are currently disabled.
+	pub fn disabled_families(&self) -> HashSet<MetricFamily> {
+		self.prometheus.lock().disabled_families.clone()
+	}
+
+	/// Stores `snapshot` as the last one and sends it to the subscribers.
+	fn publish(&self, snapshot: MetricsSnapshot) {
+		let mut subscribers = self.subscribers.lock();
+		*subscribers = std::mem::take(&mut *subscribers).into_iter()
+			.filter_map(|mut sink| match sink.try_send(snapshot.clone()) {
+				Err(err) if err.is_disconnected() => None,
+				_ => Some(sink),
+			})
+			.collect();
+		*self.snapshot.write() = snapshot;
 	}
 }
 
@@ -107,52 +799,94 @@ impl PrometheusMetrics {
 /// network state to the telemetry as well as (optionally)
 /// a Prometheus endpoint.
 pub struct MetricsService {
-	metrics: Option<PrometheusMetrics>,
 	last_update: Instant,
+	/// Interval the timer was last armed with, i.e. the intended duration of the next interval.
+	scheduled_interval: Option<Duration>,
+	/// Whether `update` was called before, i.e. `last_update` is the time of an update.
+	ticked: bool,
 	last_total_bytes_inbound: u64,
 	last_total_bytes_outbound: u64,
+	import_rate: ImportRate,
+	/// Highest finalized block number seen and when it was first seen.
+	last_finalized: Option<(u64, Instant)>,
+	handle: MetricsHandle,
+	/// Whether to log the metrics after the next update.
+	dry_run: bool,
 	telemetry: Option<TelemetryHandle>,
+	telemetry_fields: TelemetryFields,
 }
 
 impl MetricsService {
 	/// Creates a `MetricsService` that only sends information
 	/// to the telemetry.
 	pub fn new(telemetry: Option<TelemetryHandle>) -> Self {
+		Self::with_metrics(telemetry, None)
+	}
+
+	fn with_metrics(
+		telemetry: Option<TelemetryHandle>,
+		metrics: Option<PrometheusMetrics>,
+	) -> Self {
+		let handle = MetricsHandle::default();
+		handle.prometheus.lock().metrics = metrics;
 		MetricsService {
-			metrics: None,
 			last_total_bytes_inbound: 0,
 			last_total_bytes_outbound: 0,
+			import_rate: ImportRate::default(),
+			last_finalized: None,
+			handle,
+			dry_run: false,
 			last_update: Instant::now(),
+			scheduled_interval: None,
+			ticked: false,
 			telemetry,
+			telemetry_fields: Default::default(),
 		}
 	}
 
+	/// Only send the selected fields in the periodic telemetry message.
+	pub fn with_telemetry_fields(mut self, fields: TelemetryFields) -> Self {
+		self.telemetry_fields = fields;
+		self
+	}
+
 	/// Creates a `MetricsService` that sends metrics
 	/// to prometheus alongside the telemetry.
+	///
+	/// `genesis_hash` is used to tell apart metrics of nodes running different chains.
 	pub fn with_prometheus(
 		telemetry: Option<TelemetryHandle>,
 		registry: &Registry,
 		config: &Configuration,
+		genesis_hash: &str,
 	) -> Result<Self, PrometheusError> {
-		let role_bits = match config.role {
-			Role::Full => 1u64,
-			Role::Light => 2u64,
-			Role::Authority { .. } => 4u64,
-		};
+		PrometheusMetrics::setup(registry, NodeInfo::new(config, genesis_hash))
+			.map(|p| Self::with_metrics(telemetry, Some(p)))
+	}
 
-		PrometheusMetrics::setup(
-			registry,
-			&config.network.node_name,
-			&config.impl_version,
-			role_bits,
-		)
-		.map(|p| MetricsService {
-			metrics: Some(p),
-			last_total_bytes_inbound: 0,
-			last_total_bytes_outbound: 0,
-			last_update: Instant::now(),
-			telemetry,
-		})
+	/// Logs the registered metric families and their values once, after the first update.
+	///
+	/// See [`MetricsHandle::log_values`].
+	pub fn with_dry_run(mut self) -> Self {
+		self.dry_run = true;
+		self
+	}
+
+	/// Reports to `handle` instead of the handle the service was created with.
+	///
+	/// The Prometheus metrics of the service move over to `handle`. The other settings made
+	/// through the previous handle, e.g. the tick interval, are replaced by the ones of `handle`.
+	pub fn with_handle(mut self, handle: MetricsHandle) -> Self {
+		let prometheus = std::mem::take(&mut *self.handle.prometheus.lock());
+		*handle.prometheus.lock() = prometheus;
+		self.handle = handle;
+		self
+	}
+
+	/// Returns a handle to the service, e.g. to read the values reported by the updates or to
+	/// change its settings once the service runs.
+	pub fn handle(&self) -> MetricsHandle {
+		self.handle.clone()
 	}
 
 	/// Returns a never-ending `Future` that performs the
@@ -165,14 +899,15 @@ impl MetricsService {
 		network: Arc<NetworkService<TBl, <TBl as Block>::Hash>>,
 	) where
 		TBl: Block,
-		TCl: ProvideRuntimeApi<TBl> + UsageProvider<TBl>,
+		TCl: ProvideRuntimeApi<TBl> + UsageProvider<TBl> + BlockBackend<TBl> + CallApiAt<TBl>,
 		TExPool: MaintainedTransactionPool<Block = TBl, Hash = <TBl as Block>::Hash>,
 	{
 		let mut timer = Delay::new(Duration::from_secs(0));
-		let timer_interval = Duration::from_secs(5);
 
 		let net_state_duration = Duration::from_secs(30);
 		let mut last_net_state = Instant::now();
+		let mut last_best_hash = None;
+		let mut last_finalized_hash = None;
 
 		loop {
 			// Wait for the next tick of the timer.
@@ -190,18 +925,63 @@ impl MetricsService {
 			};
 
 			// Update / Send the metrics.
+			let info = client.usage_info();
 			self.update(
-				&client.usage_info(),
+				&info,
 				&transactions.status(),
 				net_status,
 				net_state,
 			);
 
+			// Only read the block body and the runtime version if they're going to be reported.
+			let finalized_hash = info.chain.finalized_hash;
+			let best_hash = info.chain.best_hash;
+			let (read_body, read_version) = {
+				let prometheus = self.handle.prometheus.lock();
+				let paused = self.handle.pause.is_paused();
+				let report = |family| !paused && prometheus.reporting(family).is_some();
+				(
+					// The body only needs to be read again once the finalized block changes, or
+					// once the reported count was reset.
+					report(MetricFamily::FinalizedBlockExtrinsics) &&
+						(last_finalized_hash != Some(finalized_hash) ||
+							!prometheus.finalized_body_reported),
+					// The runtime can only change with the best block, its series can also be
+					// reset.
+					report(MetricFamily::RuntimeVersion) &&
+						(last_best_hash != Some(best_hash) || prometheus.runtime_version.is_none()),
+				)
+			};
+
+			if read_body {
+				last_finalized_hash = Some(finalized_hash);
+				let body = client.block_body(&BlockId::Hash(finalized_hash));
+				self.update_finalized_body(body.ok().flatten().as_deref());
+			}
+
+			if read_version {
+				last_best_hash = Some(best_hash);
+				if let Ok(version) = client.runtime_version_at(&BlockId::Hash(best_hash)) {
+					self.update_runtime_version(version.spec_version, version.impl_version);
+				}
+			}
+
+			if std::mem::take(&mut self.dry_run) {
+				self.handle.log_values();
+			}
+
 			// Schedule next tick.
-			timer.reset(timer_interval);
+			timer.reset(self.schedule_next());
 		}
 	}
 
+	/// Returns the interval until the next update, and remembers it to measure the drift.
+	fn schedule_next(&mut self) -> Duration {
+		let interval = self.handle.tick_interval.get();
+		self.scheduled_interval = Some(interval);
+		interval
+	}
+
 	fn update<T: Block>(
 		&mut self,
 		info: &ClientInfo<T>,
@@ -209,58 +989,150 @@ impl MetricsService {
 		net_status: Option<NetworkStatus<T>>,
 		net_state: Option<NetworkState>,
 	) {
+		let scheduled_interval = self.scheduled_interval.take();
+		let paused = self.handle.pause.is_paused();
+		let prometheus = self.handle.prometheus.lock();
+		if let Some(metrics) = prometheus.reporting(MetricFamily::Paused) {
+			metrics.paused.set(paused as u64);
+		}
+		if paused {
+			// The interval following the pause isn't a tick interval, don't report it as drift.
+			self.ticked = false;
+			return
+		}
+
 		let now = Instant::now();
-		let elapsed = (now - self.last_update).as_secs();
+		let interval = now - self.last_update;
+		let elapsed_millis = u64::try_from(interval.as_millis()).unwrap_or(u64::MAX);
 		self.last_update = now;
+		let ticked = std::mem::replace(&mut self.ticked, true);
 
 		let best_number = info.chain.best_number.saturated_into::<u64>();
+		self.import_rate.note(best_number, interval);
 		let best_hash = info.chain.best_hash;
 		let finalized_number: u64 = info.chain.finalized_number.saturated_into::<u64>();
+		let finalized_since = match self.last_finalized {
+			Some((number, since)) if finalized_number <= number => since,
+			_ => {
+				self.last_finalized = Some((finalized_number, now));
+				now
+			},
+		};
+
+		let mut snapshot = MetricsSnapshot {
+			best_number,
+			finalized_number,
+			number_leaves: info.chain.number_leaves as u64,
+			ready_transactions: txpool_status.ready as u64,
+			state_cache_bytes: info.usage.as_ref()
+				.map(|usage| usage.memory.state_cache.as_bytes() as u64),
+			database_cache_bytes: info.usage.as_ref()
+				.map(|usage| usage.memory.database_cache.as_bytes() as u64),
+			..Default::default()
+		};
 
 		// Update/send metrics that are always available.
-		telemetry!(
-			self.telemetry;
-			SUBSTRATE_INFO;
-			"system.interval";
-			"height" => best_number,
-			"best" => ?best_hash,
-			"txcount" => txpool_status.ready,
-			"finalized_height" => finalized_number,
-			"finalized_hash" => ?info.chain.finalized_hash,
-			"used_state_cache_size" => info.usage.as_ref()
+		self.send_interval(vec![
+			("height", best_number.into()),
+			("best", format!("{:?}", best_hash).into()),
+			("txcount", txpool_status.ready.into()),
+			("finalized_height", finalized_number.into()),
+			("finalized_hash", format!("{:?}", info.chain.finalized_hash).into()),
+			("used_state_cache_size", info.usage.as_ref()
 				.map(|usage| usage.memory.state_cache.as_bytes())
-				.unwrap_or(0),
-		);
+				.unwrap_or(0)
+				.into()),
+		]);
+
+		let enabled = |family: MetricFamily| !prometheus.disabled_families.contains(&family);
+
+		if let Some(metrics) = prometheus.metrics.as_ref() {
+			if enabled(MetricFamily::BlockHeight) {
+				metrics
+					.block_height
+					.with_label_values(&["finalized"])
+					.set(finalized_number);
+				metrics
+					.block_height
+					.with_label_values(&["best"])
+					.set(best_number);
+			}
+
+			if enabled(MetricFamily::FinalityStall) {
+				metrics.finality_stall.set((now - finalized_since).as_secs());
+			}
 
-		if let Some(metrics) = self.metrics.as_ref() {
-			metrics
-				.block_height
-				.with_label_values(&["finalized"])
-				.set(finalized_number);
-			metrics
-				.block_height
-				.with_label_values(&["best"])
-				.set(best_number);
-
-			if let Ok(leaves) = u64::try_from(info.chain.number_leaves) {
-				metrics.number_leaves.set(leaves);
+			if enabled(MetricFamily::NumberLeaves) {
+				if let Ok(leaves) = u64::try_from(info.chain.number_leaves) {
+					metrics.number_leaves.set(leaves);
+				}
 			}
 
-			metrics.ready_transactions_number.set(txpool_status.ready as u64);
+			if enabled(MetricFamily::ReadyTransactions) {
+				metrics.ready_transactions_number.set(txpool_status.ready as u64);
+			}
+
+			if enabled(MetricFamily::TxpoolBytes) {
+				metrics.txpool_bytes.with_label_values(&["ready"])
+					.set(txpool_status.ready_bytes as u64);
+				metrics.txpool_bytes.with_label_values(&["future"])
+					.set(txpool_status.future_bytes as u64);
+			}
 
 			if let Some(info) = info.usage.as_ref() {
-				metrics.database_cache.set(info.memory.database_cache.as_bytes() as u64);
-				metrics.state_cache.set(info.memory.state_cache.as_bytes() as u64);
-
-				metrics.state_db.with_label_values(&["non_canonical"]).set(
-					info.memory.state_db.non_canonical.as_bytes() as u64,
-				);
-				if let Some(pruning) = info.memory.state_db.pruning {
-					metrics.state_db.with_label_values(&["pruning"]).set(pruning.as_bytes() as u64);
+				if enabled(MetricFamily::DatabaseCache) {
+					metrics.database_cache.set(info.memory.database_cache.as_bytes() as u64);
+				}
+				if enabled(MetricFamily::StateCache) {
+					metrics.state_cache.set(info.memory.state_cache.as_bytes() as u64);
+				}
+
+				if enabled(MetricFamily::StateDb) {
+					let state_db = &info.memory.state_db;
+					metrics.state_db.with_label_values(&["non_canonical"])
+						.set(state_db.non_canonical.as_bytes() as u64);
+					if let Some(pruning) = state_db.pruning {
+						metrics.state_db.with_label_values(&["pruning"])
+							.set(pruning.as_bytes() as u64);
+					} else {
+						// Don't keep reporting the last known size once pruning info goes away.
+						let _ = metrics.state_db.remove_label_values(&["pruning"]);
+					}
+					metrics.state_db.with_label_values(&["pinned"])
+						.set(state_db.pinned.as_bytes() as u64);
+				}
+				if enabled(MetricFamily::StateDbPinned) {
+					metrics.state_db_pinned.set(info.memory.state_db.pinned_blocks);
 				}
-				metrics.state_db.with_label_values(&["pinned"]).set(
-					info.memory.state_db.pinned.as_bytes() as u64,
-				);
+			}
+
+			if enabled(MetricFamily::SubsystemMemory) {
+				let subsystem_memory = &metrics.subsystem_memory;
+				self.handle.memory_providers.lock().retain(|(subsystem, provider)| {
+					// The node's panic hook exits the process, unwind instead for the provider.
+					let _guard = sp_panic_handler::AbortGuard::force_unwind();
+					match panic::catch_unwind(AssertUnwindSafe(provider)) {
+						Ok(bytes) => {
+							subsystem_memory.with_label_values(&[subsystem]).set(bytes);
+							true
+						},
+						Err(_) => {
+							log::warn!(
+								"Memory provider of subsystem {} panicked, removing it",
+								subsystem,
+							);
+							let _ = subsystem_memory.remove_label_values(&[subsystem]);
+							false
+						},
+					}
+				});
+			}
+
+			let report_depth = enabled(MetricFamily::MaxUnboundedChannelDepth);
+			let report_channel = enabled(MetricFamily::MaxUnboundedChannel);
+			if report_depth || report_channel {
+				let largest = largest_channel(unbounded_channels_depth());
+				metrics.report_largest_channel(largest, report_depth, report_channel);
 			}
 		}
 
@@ -273,30 +1145,77 @@ impl MetricsService {
 			let diff_bytes_inbound = total_bytes_inbound - self.last_total_bytes_inbound;
 			let diff_bytes_outbound = total_bytes_outbound - self.last_total_bytes_outbound;
 			let (avg_bytes_per_sec_inbound, avg_bytes_per_sec_outbound) =
-				if elapsed > 0 {
+				if elapsed_millis > 0 {
 					self.last_total_bytes_inbound = total_bytes_inbound;
 					self.last_total_bytes_outbound = total_bytes_outbound;
-					(diff_bytes_inbound / elapsed, diff_bytes_outbound / elapsed)
+					(
+						diff_bytes_inbound.saturating_mul(1000) / elapsed_millis,
+						diff_bytes_outbound.saturating_mul(1000) / elapsed_millis,
+					)
 				} else {
 					(diff_bytes_inbound, diff_bytes_outbound)
 				};
 
-			telemetry!(
-				self.telemetry;
-				SUBSTRATE_INFO;
-				"system.interval";
-				"peers" => num_peers,
-				"bandwidth_download" => avg_bytes_per_sec_inbound,
-				"bandwidth_upload" => avg_bytes_per_sec_outbound,
-			);
+			self.send_interval(vec![
+				("peers", num_peers.into()),
+				("bandwidth_download", avg_bytes_per_sec_inbound.into()),
+				("bandwidth_upload", avg_bytes_per_sec_outbound.into()),
+			]);
+
+			let best_seen_block: Option<u64> = net_status
+				.best_seen_block
+				.map(|num: NumberFor<T>| UniqueSaturatedInto::<u64>::unique_saturated_into(num));
 
-			if let Some(metrics) = self.metrics.as_ref() {
-				let best_seen_block: Option<u64> = net_status
-					.best_seen_block
-					.map(|num: NumberFor<T>| UniqueSaturatedInto::<u64>::unique_saturated_into(num));
+			snapshot.sync_target = best_seen_block;
+			snapshot.peers = Some(num_peers as u64);
+			snapshot.bandwidth_download = Some(avg_bytes_per_sec_inbound);
+			snapshot.bandwidth_upload = Some(avg_bytes_per_sec_outbound);
 
-				if let Some(best_seen_block) = best_seen_block {
-					metrics.block_height.with_label_values(&["sync_target"]).set(best_seen_block);
+			if let Some(metrics) = prometheus.metrics.as_ref() {
+				// The sync target follows the connected peers and can go down, even below the best
+				// block. It's reported as is, the ETA below is left untouched in that case.
+				if enabled(MetricFamily::BlockHeight) {
+					if let Some(best_seen_block) = best_seen_block {
+						metrics.block_height.with_label_values(&["sync_target"])
+							.set(best_seen_block);
+					} else {
+						let _ = metrics.block_height.remove_label_values(&["sync_target"]);
+					}
+				}
+
+				let syncing_to = best_seen_block.filter(|target| *target > best_number);
+				if let (true, Some(target)) = (enabled(MetricFamily::SyncEta), syncing_to) {
+					// Nothing can be estimated before the first interval was measured. Past that, a
+					// sync that doesn't progress never reaches the target.
+					if self.import_rate.has_samples() {
+						let eta = self.import_rate
+							.eta(best_number, target)
+							.map_or(f64::INFINITY, |eta| eta.as_secs_f64());
+						metrics.sync_eta.set(eta);
+					}
+				}
+			}
+		}
+
+		self.handle.publish(snapshot);
+
+		if let Some(metrics) = prometheus.metrics.as_ref() {
+			if enabled(MetricFamily::LastUpdate) {
+				let since_epoch = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)
+					.unwrap_or_default();
+				metrics.last_update.set(since_epoch.as_secs());
+			}
+
+			if enabled(MetricFamily::TickInterval) {
+				metrics.tick_interval.set(self.handle.tick_interval.get().as_secs_f64());
+			}
+
+			// The first interval is measured from the creation of the service, not from a tick.
+			// The interval may have been changed since the timer was armed, the drift is measured
+			// against the interval it was armed with.
+			if let (true, Some(scheduled)) = (ticked, scheduled_interval) {
+				if enabled(MetricFamily::TickDrift) {
+					metrics.tick_drift.set(interval.as_secs_f64() - scheduled.as_secs_f64());
 				}
 			}
 		}
@@ -311,4 +1230,937 @@ impl MetricsService {
 			);
 		}
 	}
+
+	/// Reports the number of extrinsics in the `body` of the last finalized block.
+	///
+	/// The last reported count is kept if the body isn't available, e.g. because it was pruned.
+	fn update_finalized_body<E>(&mut self, body: Option<&[E]>) {
+		let mut prometheus = self.handle.prometheus.lock();
+		let metrics = prometheus.reporting(MetricFamily::FinalizedBlockExtrinsics);
+		if let (Some(metrics), Some(body)) = (metrics, body) {
+			metrics.finalized_block_extrinsics.set(body.len() as u64);
+			prometheus.finalized_body_reported = true;
+		}
+	}
+
+	/// Reports the version of the runtime at the best block.
+	///
+	/// The series of the previous version is removed on a runtime upgrade.
+	fn update_runtime_version(&mut self, spec_version: u32, impl_version: u32) {
+		let mut prometheus = self.handle.prometheus.lock();
+		let gauge = match prometheus.reporting(MetricFamily::RuntimeVersion) {
+			Some(metrics) => metrics.runtime_version.clone(),
+			None => return,
+		};
+
+		let version = (spec_version, impl_version);
+		if let Some(previous) = prometheus.runtime_version.replace(version) {
+			if previous == version {
+				return
+			}
+			let _ = gauge.remove_label_values(&[&previous.0.to_string(), &previous.1.to_string()]);
+		}
+		gauge.with_label_values(&[&spec_version.to_string(), &impl_version.to_string()]).set(1);
+	}
+
+	/// Sends the selected `fields` as a `system.interval` telemetry message.
+	fn send_interval(&self, fields: Vec<(&'static str, serde_json::Value)>) {
+		if let Some(telemetry) = self.telemetry.as_ref() {
+			let mut payload = interval_payload(&self.telemetry_fields, fields);
+			if payload.is_empty() {
+				return;
+			}
+
+			payload.insert("msg".into(), "system.interval".into());
+			telemetry.send_telemetry(SUBSTRATE_INFO, payload);
+		}
+	}
+}
+
+/// Number of updates the import rate is averaged over.
+const IMPORT_RATE_WINDOW: usize = 6;
+
+/// Rate at which the best block advances, averaged over the last few updates.
+#[derive(Default)]
+struct ImportRate {
+	last_best_number: Option<u64>,
+	/// Number of imported blocks and the time it took, per update.
+	samples: VecDeque<(u64, Duration)>,
+}
+
+impl ImportRate {
+	/// Records the best block number observed after `interval` elapsed since the last call.
+	fn note(&mut self, best_number: u64, interval: Duration) {
+		if let Some(last) = self.last_best_number.replace(best_number) {
+			if self.samples.len() == IMPORT_RATE_WINDOW {
+				self.samples.pop_front();
+			}
+			self.samples.push_back((best_number.saturating_sub(last), interval));
+		}
+	}
+
+	/// Returns whether at least one interval was measured.
+	fn has_samples(&self) -> bool {
+		!self.samples.is_empty()
+	}
+
+	/// Returns the average number of imported blocks per second.
+	fn per_second(&self) -> Option<f64> {
+		let (blocks, time) = self.samples.iter()
+			.fold((0, Duration::default()), |(blocks, time), (b, t)| (blocks + b, time + *t));
+		if blocks == 0 || time.as_secs_f64() <= 0.0 {
+			return None
+		}
+
+		Some(blocks as f64 / time.as_secs_f64())
+	}
+
+	/// Estimates how long it takes to get from `best_number` to `target` at the current rate.
+	///
+	/// Returns `None` if there is nothing to sync or no blocks were imported recently.
+	fn eta(&self, best_number: u64, target: u64) -> Option<Duration> {
+		if target <= best_number {
+			return None
+		}
+
+		self.per_second()
+			.map(|rate| Duration::from_secs_f64((target - best_number) as f64 / rate))
+	}
+}
+
+/// Describes the metric families of `collector`.
+fn describe_collector(collector: &dyn Collector) -> Vec<MetricDescription> {
+	collector.desc().into_iter()
+		.map(|desc| MetricDescription {
+			name: desc.fq_name.clone(),
+			help: desc.help.clone(),
+			labels: desc.const_label_pairs.iter()
+				.map(|pair| pair.get_name().to_string())
+				.chain(desc.variable_labels.iter().cloned())
+				.collect(),
+		})
+		.collect()
+}
+
+/// Renders the current metric families of `registry` in the text exposition format.
+fn render_text(registry: &Registry) -> Result<String, PrometheusError> {
+	let mut buffer = Vec::new();
+	TextEncoder::new().encode(&registry.gather(), &mut buffer)?;
+	String::from_utf8(buffer).map_err(|err| PrometheusError::Msg(err.to_string()))
+}
+
+/// Builds the payload of a `system.interval` telemetry message out of the selected fields.
+fn interval_payload(
+	selected: &TelemetryFields,
+	fields: Vec<(&'static str, serde_json::Value)>,
+) -> serde_json::Map<String, serde_json::Value> {
+	fields.into_iter()
+		.filter(|(key, _)| selected.contains(key))
+		.map(|(key, value)| (key.into(), value))
+		.collect()
+}
+
+/// Returns the channel with the largest backlog, `None` if all channels are empty.
+///
+/// Ties are broken in favour of the lexicographically smallest channel name.
+fn largest_channel(depths: impl IntoIterator<Item = (String, u64)>) -> Option<(String, u64)> {
+	depths.into_iter()
+		.filter(|(_, depth)| *depth > 0)
+		.max_by(|(a_name, a_depth), (b_name, b_depth)| {
+			a_depth.cmp(b_depth).then_with(|| b_name.cmp(a_name))
+		})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::{ClientInfoBuilder, NetworkStatusBuilder, PoolStatusBuilder};
+	use futures::{executor::block_on, StreamExt};
+	use sc_client_api::{MemorySize, UsageInfo};
+	use substrate_test_runtime_client::runtime::{Block, Extrinsic};
+
+	fn node_info(database: &str) -> NodeInfo {
+		NodeInfo {
+			name: "test-node".into(),
+			version: "0.1.0".into(),
+			roles: 1,
+			database: database.into(),
+			genesis_hash: "0x0123456789abcdef".into(),
+			start_time: Duration::from_secs(1_600_000_000),
+		}
+	}
+
+	fn service(registry: &Registry) -> MetricsService {
+		let metrics = PrometheusMetrics::setup(registry, node_info("rocksdb"))
+			.expect("registering metrics works");
+		MetricsService::with_metrics(None, Some(metrics))
+	}
+
+	fn client_info(best: u64, finalized: u64, usage: Option<UsageInfo>) -> ClientInfo<Block> {
+		let info = ClientInfoBuilder::new().best_number(best).finalized_number(finalized);
+		match usage {
+			Some(usage) => info.usage(usage),
+			None => info,
+		}.build()
+	}
+
+	fn pool_status(ready: usize) -> PoolStatus {
+		PoolStatusBuilder::new().ready(ready, 0).build()
+	}
+
+	fn net_status(best_seen_block: Option<u64>) -> NetworkStatus<Block> {
+		NetworkStatusBuilder::new().best_seen_block(best_seen_block).build()
+	}
+
+	/// Returns the value of the gauge `name`, optionally restricted to the series with `label`.
+	fn gauge(registry: &Registry, name: &str, label: Option<(&str, &str)>) -> Option<f64> {
+		registry.gather().iter()
+			.filter(|family| family.get_name() == name)
+			.flat_map(|family| family.get_metric().iter())
+			.find(|metric| label.map_or(true, |(key, value)| metric.get_label().iter()
+				.any(|pair| pair.get_name() == key && pair.get_value() == value)
+			))
+			.map(|metric| metric.get_gauge().get_value())
+	}
+
+	#[test]
+	fn update_sets_gauges_from_inputs() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+
+		let mut usage = UsageInfo::default();
+		usage.memory.state_cache = MemorySize::from_bytes(1000);
+		usage.memory.database_cache = MemorySize::from_bytes(2000);
+		usage.memory.state_db.non_canonical = MemorySize::from_bytes(3000);
+		usage.memory.state_db.pinned = MemorySize::from_bytes(4000);
+		usage.memory.state_db.pinned_blocks = 5;
+		let info = ClientInfoBuilder::new()
+			.best_number(12)
+			.finalized_number(10)
+			.leaves(3)
+			.usage(usage)
+			.build();
+		service.update(&info, &pool_status(7), Some(net_status(Some(15))), None);
+
+		let height = |status| gauge(&registry, "block_height", Some(("status", status)));
+		assert_eq!(height("best"), Some(12.0));
+		assert_eq!(height("finalized"), Some(10.0));
+		assert_eq!(height("sync_target"), Some(15.0));
+		assert_eq!(gauge(&registry, "number_leaves", None), Some(3.0));
+		assert_eq!(gauge(&registry, "ready_transactions_number", None), Some(7.0));
+		assert_eq!(gauge(&registry, "state_cache_bytes", None), Some(1000.0));
+		assert_eq!(gauge(&registry, "database_cache_bytes", None), Some(2000.0));
+		let state_db = |subtype| {
+			gauge(&registry, "state_db_cache_bytes", Some(("subtype", subtype)))
+		};
+		assert_eq!(state_db("non_canonical"), Some(3000.0));
+		assert_eq!(state_db("pinned"), Some(4000.0));
+		assert_eq!(state_db("pruning"), None);
+		assert_eq!(gauge(&registry, "state_db_pinned_count", None), Some(5.0));
+	}
+
+	#[test]
+	fn txpool_bytes_are_reported_by_queue() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+
+		let status = PoolStatusBuilder::new().ready(2, 300).future(1, 4000).build();
+		service.update(&client_info(1, 0, None), &status, None, None);
+
+		let bytes = |queue| gauge(&registry, "txpool_bytes", Some(("queue", queue)));
+		assert_eq!(bytes("ready"), Some(300.0));
+		assert_eq!(bytes("future"), Some(4000.0));
+		assert_eq!(gauge(&registry, "ready_transactions_number", None), Some(2.0));
+		assert_eq!(service.handle().snapshot().ready_transactions, 2);
+	}
+
+	#[test]
+	fn chain_info_is_reported() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+
+		let info = ClientInfoBuilder::<Block>::new()
+			.best_number(42)
+			.finalized_number(40)
+			.leaves(2)
+			.build();
+		service.update(&info, &pool_status(0), None, None);
+
+		let height = |status| gauge(&registry, "block_height", Some(("status", status)));
+		assert_eq!(height("best"), Some(42.0));
+		assert_eq!(height("finalized"), Some(40.0));
+		assert_eq!(height("sync_target"), None);
+		assert_eq!(gauge(&registry, "number_leaves", None), Some(2.0));
+
+		let snapshot = service.handle().snapshot();
+		assert_eq!((snapshot.best_number, snapshot.finalized_number), (42, 40));
+		assert_eq!(snapshot.number_leaves, 2);
+		assert_eq!(snapshot.state_cache_bytes, None);
+	}
+
+	#[test]
+	fn network_status_is_reported() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+
+		service.last_update = Instant::now() - Duration::from_secs(2);
+		let status = NetworkStatusBuilder::new()
+			.best_seen_block(Some(50))
+			.connected_peers(8)
+			.total_bytes(4000, 2000)
+			.build();
+		service.update(&client_info(20, 10, None), &pool_status(0), Some(status), None);
+
+		let sync_target = Some(("status", "sync_target"));
+		assert_eq!(gauge(&registry, "block_height", sync_target), Some(50.0));
+		let snapshot = service.handle().snapshot();
+		assert_eq!(snapshot.sync_target, Some(50));
+		assert_eq!(snapshot.peers, Some(8));
+		// The bytes are averaged over the ~2 seconds since the last update.
+		let download = snapshot.bandwidth_download.unwrap();
+		let upload = snapshot.bandwidth_upload.unwrap();
+		assert!((1990..=2000).contains(&download), "unexpected download: {}", download);
+		assert!((995..=1000).contains(&upload), "unexpected upload: {}", upload);
+	}
+
+	#[test]
+	fn series_not_reported_anymore_are_removed() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+
+		let mut usage = UsageInfo::default();
+		usage.memory.state_db.pruning = Some(MemorySize::from_bytes(1024));
+		service.update(
+			&client_info(10, 8, Some(usage.clone())),
+			&pool_status(0),
+			Some(net_status(Some(20))),
+			None,
+		);
+		let pruning = Some(("subtype", "pruning"));
+		let sync_target = Some(("status", "sync_target"));
+		assert_eq!(gauge(&registry, "state_db_cache_bytes", pruning), Some(1024.0));
+		assert_eq!(gauge(&registry, "block_height", sync_target), Some(20.0));
+
+		usage.memory.state_db.pruning = None;
+		service.update(
+			&client_info(11, 9, Some(usage)),
+			&pool_status(0),
+			Some(net_status(None)),
+			None,
+		);
+		assert_eq!(gauge(&registry, "state_db_cache_bytes", pruning), None);
+		assert_eq!(gauge(&registry, "block_height", sync_target), None);
+		assert_eq!(gauge(&registry, "block_height", Some(("status", "best"))), Some(11.0));
+	}
+
+	#[test]
+	fn database_cache_is_labeled_by_backend() {
+		for backend in &["rocksdb", "paritydb"] {
+			let registry = Registry::new();
+			let metrics = PrometheusMetrics::setup(&registry, node_info(backend))
+				.expect("registering metrics works");
+			let mut service = MetricsService::with_metrics(None, Some(metrics));
+
+			let mut usage = UsageInfo::default();
+			usage.memory.database_cache = MemorySize::from_bytes(2048);
+			service.update(&client_info(1, 0, Some(usage)), &pool_status(0), None, None);
+
+			let label = Some(("backend", *backend));
+			assert_eq!(gauge(&registry, "database_cache_bytes", label), Some(2048.0));
+		}
+	}
+
+	#[test]
+	fn genesis_hash_is_reported() {
+		let registry = Registry::new();
+		let _service = service(&registry);
+
+		let label = Some(("genesis_hash", "0x0123456789abcdef"));
+		assert_eq!(gauge(&registry, "chain_genesis_info", label), Some(1.0));
+	}
+
+	#[test]
+	fn rebind_reports_to_the_new_registry() {
+		let first = Registry::new();
+		let mut service = service(&first);
+		let handle = service.handle();
+
+		// The service is owned by the task running it, only the handle is left to reconfigure it.
+		let (tick, ticks) = std::sync::mpsc::channel::<u64>();
+		let (ticked, updates) = std::sync::mpsc::channel();
+		let running = std::thread::spawn(move || {
+			for best in ticks {
+				service.update(&client_info(best, 0, None), &pool_status(0), None, None);
+				ticked.send(()).unwrap();
+			}
+		});
+		let update = |best| {
+			tick.send(best).unwrap();
+			updates.recv().unwrap();
+		};
+		update(1);
+
+		let second = Registry::new();
+		handle.rebind(&second).expect("registering in a fresh registry works");
+		update(2);
+
+		let best = Some(("status", "best"));
+		assert_eq!(gauge(&first, "block_height", best), Some(1.0));
+		assert_eq!(gauge(&second, "block_height", best), Some(2.0));
+		assert_eq!(gauge(&second, "process_start_time_seconds", None), Some(1_600_000_000.0));
+
+		// Registering twice in the same registry fails and keeps the current metrics.
+		assert!(handle.rebind(&second).is_err());
+		update(3);
+		assert_eq!(gauge(&second, "block_height", best), Some(3.0));
+
+		// A failure halfway doesn't leave the metrics registered until then behind.
+		let third = Registry::new();
+		register(Gauge::<U64>::new("number_leaves", "Taken").unwrap(), &third).unwrap();
+		assert!(handle.rebind(&third).is_err());
+		let families = third.gather();
+		let names: Vec<_> = families.iter().map(|family| family.get_name()).collect();
+		assert_eq!(names, vec!["number_leaves"]);
+
+		drop(tick);
+		running.join().unwrap();
+		assert!(MetricsService::new(None).handle().rebind(&second).is_err());
+	}
+
+	#[test]
+	fn disabled_families_are_not_updated() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+		let handle = service.handle();
+		let best = Some(("status", "best"));
+
+		service.update(&client_info(1, 0, None), &pool_status(5), None, None);
+		assert_eq!(gauge(&registry, "block_height", best), Some(1.0));
+
+		handle.disable_family(MetricFamily::BlockHeight).unwrap();
+		handle.disable_family(MetricFamily::ReadyTransactions).unwrap();
+		assert_eq!(gauge(&registry, "block_height", best), None);
+		assert_eq!(gauge(&registry, "ready_transactions_number", None), None);
+
+		service.update(&client_info(2, 1, None), &pool_status(6), None, None);
+		assert_eq!(gauge(&registry, "block_height", best), None);
+		assert_eq!(gauge(&registry, "ready_transactions_number", None), None);
+		assert_eq!(gauge(&registry, "number_leaves", None), Some(1.0));
+
+		handle.enable_family(MetricFamily::BlockHeight).unwrap();
+		handle.enable_family(MetricFamily::ReadyTransactions).unwrap();
+		assert_eq!(gauge(&registry, "block_height", best), None);
+		service.update(&client_info(3, 2, None), &pool_status(7), None, None);
+		assert_eq!(gauge(&registry, "block_height", best), Some(3.0));
+		assert_eq!(gauge(&registry, "ready_transactions_number", None), Some(7.0));
+
+		// Disabled families stay out of a new registry.
+		handle.disable_family(MetricFamily::NumberLeaves).unwrap();
+		let second = Registry::new();
+		handle.rebind(&second).unwrap();
+		service.update(&client_info(4, 3, None), &pool_status(7), None, None);
+		assert_eq!(gauge(&second, "number_leaves", None), None);
+		assert_eq!(gauge(&second, "block_height", best), Some(4.0));
+	}
+
+	#[test]
+	fn unknown_families_are_rejected() {
+		let registry = Registry::new();
+		let service = service(&registry);
+		let handle = service.handle();
+
+		assert!("block_heigth".parse::<MetricFamily>().is_err());
+		assert!("build_info".parse::<MetricFamily>().is_err());
+		assert_eq!("block_height".parse(), Ok(MetricFamily::BlockHeight));
+		let disabled = MetricsService::new(None).handle().disable_family(MetricFamily::BlockHeight);
+		assert!(disabled.is_err());
+
+		// Disabling twice is fine.
+		handle.disable_family(MetricFamily::BlockHeight).unwrap();
+		handle.disable_family(MetricFamily::BlockHeight).unwrap();
+		let disabled: HashSet<_> = vec![MetricFamily::BlockHeight].into_iter().collect();
+		assert_eq!(handle.disabled_families(), disabled);
+	}
+
+	#[test]
+	fn family_names_match_the_registered_metrics() {
+		let registry = Registry::new();
+		let service = service(&registry);
+		let prometheus = service.handle.prometheus.lock();
+		let metrics = prometheus.metrics.as_ref().unwrap();
+
+		for family in MetricFamily::ALL.iter() {
+			let names: Vec<_> = metrics.family(*family).collector().desc().iter()
+				.map(|desc| desc.fq_name.clone())
+				.collect();
+			assert_eq!(names, vec![family.name().to_string()]);
+			assert_eq!(family.name().parse(), Ok(*family));
+		}
+	}
+
+	#[test]
+	fn snapshot_is_available_as_json() {
+		let mut service = MetricsService::new(None);
+		let handle = service.handle();
+		assert_eq!(handle.snapshot(), MetricsSnapshot::default());
+
+		let mut usage = UsageInfo::default();
+		usage.memory.state_cache = MemorySize::from_bytes(4096);
+		let mut status = net_status(Some(42));
+		status.num_connected_peers = 7;
+		service.update(&client_info(10, 8, Some(usage)), &pool_status(3), Some(status), None);
+
+		let json = handle.metrics_json();
+		assert_eq!(json["bestNumber"], 10);
+		assert_eq!(json["finalizedNumber"], 8);
+		assert_eq!(json["syncTarget"], 42);
+		assert_eq!(json["readyTransactions"], 3);
+		assert_eq!(json["peers"], 7);
+		assert_eq!(json["stateCacheBytes"], 4096);
+		assert_eq!(json["databaseCacheBytes"], 0);
+
+		service.update(&client_info(11, 8, None), &pool_status(0), None, None);
+		let json = handle.metrics_json();
+		assert_eq!(json["bestNumber"], 11);
+		assert!(json["peers"].is_null());
+		assert!(json["stateCacheBytes"].is_null());
+	}
+
+	#[test]
+	fn subscribers_receive_every_snapshot() {
+		let mut service = MetricsService::new(None);
+		let handle = service.handle();
+		let first = handle.subscribe();
+		let second = handle.subscribe();
+
+		service.update(&client_info(1, 0, None), &pool_status(0), None, None);
+		service.update(&client_info(2, 1, None), &pool_status(0), None, None);
+		drop((service, handle));
+
+		let first: Vec<_> = block_on(first.map(|snapshot| snapshot.best_number).collect());
+		let second: Vec<_> = block_on(second.map(|snapshot| snapshot.best_number).collect());
+		assert_eq!(first, vec![1, 2]);
+		assert_eq!(second, vec![1, 2]);
+	}
+
+	#[test]
+	fn describe_lists_registered_families() {
+		let registry = Registry::new();
+		let service = service(&registry);
+		let handle = service.handle();
+
+		let families = handle.describe();
+		let names: Vec<_> = families.iter().map(|family| family.name.as_str()).collect();
+		let globals = sp_utils::metrics::globals();
+		let globals = globals.iter()
+			.flat_map(|global| global.desc())
+			.map(|desc| desc.fq_name.as_str());
+		let mut expected: Vec<_> = MetricFamily::ALL.iter()
+			.map(|family| family.name())
+			.chain(vec![
+				"build_info",
+				"chain_genesis_info",
+				"node_roles",
+				"process_start_time_seconds",
+			])
+			.chain(globals)
+			.collect();
+		expected.sort();
+		// This includes the families without any series yet, e.g. `max_unbounded_channel`.
+		assert_eq!(names, expected);
+
+		let family = |name| families.iter().find(|family| family.name == name);
+		let block_height = family("block_height").unwrap();
+		assert_eq!(block_height.help, "Block height info of the chain");
+		assert_eq!(block_height.labels, vec!["status"]);
+		assert_eq!(family("build_info").unwrap().labels, vec!["name", "version"]);
+		assert!(family("metrics_last_tick_unixtime").unwrap().labels.is_empty());
+		// Constant labels of the registry can't collide with the labels of the node.
+		for label in families.iter().flat_map(|family| &family.labels) {
+			let reserved = crate::config::RESERVED_LABEL_NAMES;
+			assert!(reserved.contains(&label.as_str()), "{:?} isn't reserved", label);
+		}
+		assert_eq!(family("subsystem_memory_bytes").unwrap().labels, vec!["subsystem"]);
+
+		handle.disable_family(MetricFamily::NumberLeaves).unwrap();
+		assert!(handle.describe().iter().all(|family| family.name != "number_leaves"));
+
+		assert!(MetricsService::new(None).handle().describe().is_empty());
+	}
+
+	#[test]
+	fn prometheus_text_contains_the_updated_values() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+		service.update(&client_info(12, 10, None), &pool_status(7), None, None);
+		let handle = service.handle();
+		drop(service);
+
+		let text = handle.render_prometheus_text().unwrap();
+		let lines: Vec<_> = text.lines().collect();
+		assert!(lines.contains(&"# TYPE block_height gauge"));
+		assert!(lines.contains(&"block_height{status=\"best\"} 12"));
+		assert!(lines.contains(&"block_height{status=\"finalized\"} 10"));
+		assert!(lines.contains(&"ready_transactions_number 7"));
+
+		assert!(MetricsService::new(None).handle().render_prometheus_text().is_err());
+	}
+
+	#[test]
+	fn finality_stall_is_measured_from_the_last_advance() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+		let stall = || gauge(&registry, "seconds_since_finality_advance", None);
+
+		service.update(&client_info(10, 5, None), &pool_status(0), None, None);
+		assert_eq!(stall(), Some(0.0));
+
+		// Finality stalls while new blocks keep coming in.
+		service.last_finalized = Some((5, Instant::now() - Duration::from_secs(30)));
+		service.update(&client_info(12, 5, None), &pool_status(0), None, None);
+		assert!(stall().unwrap() >= 30.0);
+
+		service.update(&client_info(13, 6, None), &pool_status(0), None, None);
+		assert_eq!(stall(), Some(0.0));
+	}
+
+	#[test]
+	fn paused_updates_keep_the_last_values() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+		let handle = service.handle();
+		let pause = handle.pause_handle();
+		service.update(&client_info(1, 0, None), &pool_status(0), None, None);
+		assert_eq!(gauge(&registry, "metrics_paused", None), Some(0.0));
+
+		pause.pause();
+		service.update(&client_info(2, 1, None), &pool_status(0), None, None);
+		assert_eq!(gauge(&registry, "metrics_paused", None), Some(1.0));
+		assert_eq!(gauge(&registry, "block_height", Some(("status", "best"))), Some(1.0));
+		assert_eq!(handle.snapshot().best_number, 1);
+
+		pause.resume();
+		service.update(&client_info(3, 2, None), &pool_status(0), None, None);
+		assert_eq!(gauge(&registry, "metrics_paused", None), Some(0.0));
+		assert_eq!(gauge(&registry, "block_height", Some(("status", "best"))), Some(3.0));
+		assert_eq!(handle.snapshot().best_number, 3);
+	}
+
+	#[test]
+	fn last_update_time_advances() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+		assert_eq!(gauge(&registry, "metrics_last_tick_unixtime", None), Some(0.0));
+
+		service.update(&client_info(1, 0, None), &pool_status(0), None, None);
+		let first = gauge(&registry, "metrics_last_tick_unixtime", None).unwrap();
+		assert!(first > 0.0);
+
+		service.update(&client_info(2, 0, None), &pool_status(0), None, None);
+		let second = gauge(&registry, "metrics_last_tick_unixtime", None).unwrap();
+		assert!(second >= first);
+	}
+
+	#[test]
+	fn tick_drift_is_reported() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+		service.handle().tick_interval().set(Duration::from_secs(5));
+
+		service.update(&client_info(1, 0, None), &pool_status(0), None, None);
+		assert_eq!(gauge(&registry, "metrics_tick_drift_seconds", None), Some(0.0));
+
+		// The loop woke up 3 seconds late.
+		service.schedule_next();
+		service.last_update = Instant::now() - Duration::from_secs(8);
+		service.update(&client_info(2, 0, None), &pool_status(0), None, None);
+		let drift = gauge(&registry, "metrics_tick_drift_seconds", None).unwrap();
+		assert!(drift >= 3.0 && drift < 4.0, "unexpected drift {}", drift);
+
+		service.schedule_next();
+		service.last_update = Instant::now() - Duration::from_secs(5);
+		service.update(&client_info(3, 0, None), &pool_status(0), None, None);
+		let drift = gauge(&registry, "metrics_tick_drift_seconds", None).unwrap();
+		assert!(drift >= 0.0 && drift < 1.0, "unexpected drift {}", drift);
+	}
+
+	#[test]
+	fn rates_follow_a_changed_interval() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+		let handle = service.handle();
+		let interval = handle.tick_interval();
+		let mut status = net_status(None);
+
+		service.update(&client_info(1, 0, None), &pool_status(0), Some(status.clone()), None);
+		assert_eq!(gauge(&registry, "metrics_tick_interval_seconds", None), Some(5.0));
+
+		// The timer is armed with 5s, then the interval is changed to 500ms.
+		service.schedule_next();
+		interval.set(Duration::from_millis(500));
+		service.last_update = Instant::now() - Duration::from_secs(5);
+		service.update(&client_info(1, 0, None), &pool_status(0), Some(status.clone()), None);
+		let drift = gauge(&registry, "metrics_tick_drift_seconds", None).unwrap();
+		assert!(drift >= 0.0 && drift < 0.1, "unexpected drift: {}", drift);
+
+		// From now on, 1000 bytes come in every 500ms.
+		for _ in 0..3 {
+			service.schedule_next();
+			status.total_bytes_inbound += 1000;
+			service.last_update = Instant::now() - Duration::from_millis(500);
+			service.update(&client_info(1, 0, None), &pool_status(0), Some(status.clone()), None);
+
+			let download = handle.snapshot().bandwidth_download.unwrap();
+			assert!(download > 1900 && download <= 2000, "unexpected bandwidth: {}", download);
+			let drift = gauge(&registry, "metrics_tick_drift_seconds", None).unwrap();
+			assert!(drift >= 0.0 && drift < 0.1, "unexpected drift: {}", drift);
+		}
+		assert_eq!(gauge(&registry, "metrics_tick_interval_seconds", None), Some(0.5));
+	}
+
+	#[test]
+	fn finalized_block_extrinsics_are_counted() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+
+		let body = vec![Extrinsic::IncludeData(vec![1]), Extrinsic::IncludeData(vec![2])];
+		service.update_finalized_body(Some(&body));
+		assert_eq!(gauge(&registry, "finalized_block_extrinsics", None), Some(2.0));
+		assert!(service.handle.prometheus.lock().finalized_body_reported);
+
+		// A pruned body leaves the last count in place.
+		service.update_finalized_body::<Extrinsic>(None);
+		assert_eq!(gauge(&registry, "finalized_block_extrinsics", None), Some(2.0));
+
+		// Once the count is reset, the body of the same finalized block is read again.
+		service.handle().disable_family(MetricFamily::FinalizedBlockExtrinsics).unwrap();
+		service.handle().enable_family(MetricFamily::FinalizedBlockExtrinsics).unwrap();
+		assert!(!service.handle.prometheus.lock().finalized_body_reported);
+	}
+
+	#[test]
+	fn runtime_upgrade_replaces_version_series() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+
+		let version = |label| gauge(&registry, "runtime_version_info", Some(label));
+
+		service.update_runtime_version(100, 1);
+		assert_eq!(version(("spec_version", "100")), Some(1.0));
+
+		service.update_runtime_version(101, 2);
+		let series = registry.gather().into_iter()
+			.find(|family| family.get_name() == "runtime_version_info")
+			.map(|family| family.get_metric().len());
+		assert_eq!(series, Some(1));
+		assert_eq!(version(("spec_version", "100")), None);
+		assert_eq!(version(("spec_version", "101")), Some(1.0));
+		assert_eq!(version(("impl_version", "2")), Some(1.0));
+	}
+
+	#[test]
+	fn runtime_version_is_reported_again_after_a_reset() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+		service.update_runtime_version(100, 1);
+
+		service.handle().disable_family(MetricFamily::RuntimeVersion).unwrap();
+		service.handle().enable_family(MetricFamily::RuntimeVersion).unwrap();
+		service.update_runtime_version(100, 1);
+		assert_eq!(
+			gauge(&registry, "runtime_version_info", Some(("spec_version", "100"))),
+			Some(1.0),
+		);
+
+		let second = Registry::new();
+		service.handle().rebind(&second).unwrap();
+		service.update_runtime_version(100, 1);
+		assert_eq!(
+			gauge(&second, "runtime_version_info", Some(("spec_version", "100"))),
+			Some(1.0),
+		);
+	}
+
+	#[test]
+	fn subsystem_memory_is_reported() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+		let handle = service.handle();
+		handle.register_memory_provider("network", Box::new(|| 1024));
+		handle.register_memory_provider("txpool", Box::new(|| 2048));
+		handle.register_memory_provider("broken", Box::new(|| panic!("provider bug")));
+
+		service.update(&client_info(1, 0, None), &pool_status(0), None, None);
+
+		let subsystem = |name| {
+			gauge(&registry, "subsystem_memory_bytes", Some(("subsystem", name)))
+		};
+		assert_eq!(subsystem("network"), Some(1024.0));
+		assert_eq!(subsystem("txpool"), Some(2048.0));
+		assert_eq!(subsystem("broken"), None);
+		assert_eq!(handle.memory_providers.lock().len(), 2);
+	}
+
+	#[test]
+	fn transaction_pool_memory_is_reported() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+		let pool = sc_transaction_pool::BasicPool::new_full(
+			Default::default(),
+			true.into(),
+			None,
+			sp_core::testing::TaskExecutor::new(),
+			Arc::new(substrate_test_runtime_client::new()),
+		);
+		service.handle().register_malloc_size_of("transaction_pool", pool.clone());
+
+		service.update(&client_info(1, 0, None), &pool_status(0), None, None);
+
+		assert_eq!(
+			gauge(&registry, "subsystem_memory_bytes", Some(("subsystem", "transaction_pool"))),
+			Some(parity_util_mem::malloc_size(&*pool) as f64),
+		);
+	}
+
+	#[test]
+	fn largest_channel_is_deterministic() {
+		let depths = vec![
+			("mpsc_b".to_string(), 7),
+			("mpsc_c".to_string(), 3),
+			("mpsc_a".to_string(), 7),
+			("mpsc_d".to_string(), 0),
+		];
+
+		assert_eq!(largest_channel(depths.clone()), Some(("mpsc_a".to_string(), 7)));
+		assert_eq!(largest_channel(depths.into_iter().rev()), Some(("mpsc_a".to_string(), 7)));
+		assert_eq!(largest_channel(Vec::new()), None);
+		assert_eq!(largest_channel(vec![("mpsc_d".to_string(), 0)]), None);
+	}
+
+	#[test]
+	fn drained_channels_are_not_reported() {
+		let registry = Registry::new();
+		let service = service(&registry);
+		let handle = service.handle();
+		let prometheus = handle.prometheus.lock();
+		let metrics = prometheus.metrics.as_ref().unwrap();
+		let channel = |entity| gauge(&registry, "max_unbounded_channel", Some(("entity", entity)));
+
+		let depths = vec![("mpsc_a".to_string(), 3), ("mpsc_b".to_string(), 1)];
+		metrics.report_largest_channel(largest_channel(depths), true, true);
+		assert_eq!(gauge(&registry, "max_unbounded_channel_depth", None), Some(3.0));
+		assert_eq!(channel("mpsc_a"), Some(1.0));
+
+		let depths = vec![("mpsc_a".to_string(), 0), ("mpsc_b".to_string(), 0)];
+		metrics.report_largest_channel(largest_channel(depths), true, true);
+		assert_eq!(gauge(&registry, "max_unbounded_channel_depth", None), Some(0.0));
+		assert_eq!(channel("mpsc_a"), None);
+		assert_eq!(channel("mpsc_b"), None);
+	}
+
+	#[test]
+	fn only_selected_telemetry_fields_are_sent() {
+		let fields = || vec![
+			("height", 10.into()),
+			("txcount", 3.into()),
+			("used_state_cache_size", 1024.into()),
+			("peers", 5.into()),
+			("bandwidth_download", 100.into()),
+		];
+
+		let payload = interval_payload(&TelemetryFields::minimal(), fields());
+		assert_eq!(payload.keys().collect::<Vec<_>>(), vec!["height", "peers"]);
+
+		let payload = interval_payload(&TelemetryFields::default(), fields());
+		assert_eq!(payload.len(), 5);
+	}
+
+	#[test]
+	fn regressing_sync_target_keeps_eta_sane() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+		let sync_target = Some(("status", "sync_target"));
+		let mut last_eta = 0.0;
+
+		for (update, target) in [200, 120, 50, 300].iter().enumerate() {
+			let best = 100 + update as u64 * 10;
+			service.last_update = Instant::now() - Duration::from_secs(5);
+			let status = Some(net_status(Some(*target)));
+			service.update(&client_info(best, 0, None), &pool_status(0), status, None);
+
+			assert_eq!(gauge(&registry, "block_height", sync_target), Some(*target as f64));
+			assert_eq!(service.handle().snapshot().sync_target, Some(*target));
+			let eta = gauge(&registry, "sync_eta_seconds", None).unwrap();
+			if *target <= best {
+				assert_eq!(eta, last_eta);
+			} else {
+				assert!(eta.is_finite() && eta >= 0.0, "unexpected eta: {}", eta);
+			}
+			last_eta = eta;
+		}
+	}
+
+	#[test]
+	fn stalled_sync_is_not_reported_as_in_sync() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+		let eta = || gauge(&registry, "sync_eta_seconds", None).unwrap();
+
+		for best in [100, 150, 150, 150].iter() {
+			service.last_update = Instant::now() - Duration::from_secs(5);
+			let status = Some(net_status(Some(1000)));
+			service.update(&client_info(*best, 0, None), &pool_status(0), status, None);
+		}
+		// 50 blocks were imported within the window.
+		assert!(eta().is_finite() && eta() > 0.0, "unexpected eta: {}", eta());
+
+		for _ in 0..IMPORT_RATE_WINDOW {
+			service.last_update = Instant::now() - Duration::from_secs(5);
+			let status = Some(net_status(Some(1000)));
+			service.update(&client_info(150, 0, None), &pool_status(0), status, None);
+		}
+		assert_eq!(eta(), f64::INFINITY);
+	}
+
+	#[test]
+	fn sync_eta_is_only_reported_while_syncing() {
+		let registry = Registry::new();
+		let mut service = service(&registry);
+		let eta = || gauge(&registry, "sync_eta_seconds", None).unwrap();
+		let mut update = |best, target| {
+			service.last_update = Instant::now() - Duration::from_millis(2500);
+			let status = Some(net_status(Some(target)));
+			service.update(&client_info(best, 0, None), &pool_status(0), status, None);
+		};
+
+		// Nothing was measured on the first update, the rate is still unknown.
+		update(100, 1000);
+		assert_eq!(eta(), 0.0);
+
+		// 25 blocks in 2.5 seconds, the fraction of a second is kept.
+		update(125, 1000);
+		assert!((eta() - 87.5).abs() < 0.1, "unexpected eta: {}", eta());
+
+		// The target is reached, the last estimate is left as is.
+		update(1000, 1000);
+		assert!((eta() - 87.5).abs() < 0.1, "unexpected eta: {}", eta());
+	}
+
+	#[test]
+	fn sync_eta_follows_import_rate() {
+		let mut rate = ImportRate::default();
+		assert_eq!(rate.eta(0, 1000), None);
+
+		// 10 blocks per second, with a single slow update that gets out of the window.
+		rate.note(0, Duration::from_secs(5));
+		rate.note(10, Duration::from_secs(10));
+		for update in 1..=IMPORT_RATE_WINDOW as u64 {
+			rate.note(10 + update * 50, Duration::from_secs(5));
+		}
+
+		let best = 10 + IMPORT_RATE_WINDOW as u64 * 50;
+		let eta = rate.eta(best, best + 1000).unwrap().as_secs_f64();
+		assert!((eta - 100.0).abs() < 0.001, "unexpected eta: {}", eta);
+		assert_eq!(rate.eta(best, best), None);
+	}
 }
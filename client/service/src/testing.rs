@@ -0,0 +1,185 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Builders of the inputs of the metrics updates, to exercise them with controlled values.
+
+use sc_client_api::{ClientInfo, UsageInfo};
+use sc_network::{NetworkStatus, SyncState};
+use sp_blockchain::Info;
+use sp_runtime::traits::{Block as BlockT, NumberFor, Zero};
+use sp_transaction_pool::PoolStatus;
+
+/// Builds a [`ClientInfo`].
+///
+/// Starts at the genesis block with default hashes, a single leaf and no usage info.
+pub struct ClientInfoBuilder<Block: BlockT>(ClientInfo<Block>);
+
+impl<Block: BlockT> ClientInfoBuilder<Block> {
+	/// Creates a new builder.
+	pub fn new() -> Self {
+		Self(ClientInfo {
+			chain: Info {
+				best_hash: Default::default(),
+				best_number: Zero::zero(),
+				genesis_hash: Default::default(),
+				finalized_hash: Default::default(),
+				finalized_number: Zero::zero(),
+				number_leaves: 1,
+			},
+			usage: None,
+		})
+	}
+
+	/// Sets the number of the best block.
+	pub fn best_number(mut self, number: NumberFor<Block>) -> Self {
+		self.0.chain.best_number = number;
+		self
+	}
+
+	/// Sets the hash of the best block.
+	pub fn best_hash(mut self, hash: Block::Hash) -> Self {
+		self.0.chain.best_hash = hash;
+		self
+	}
+
+	/// Sets the number of the last finalized block.
+	pub fn finalized_number(mut self, number: NumberFor<Block>) -> Self {
+		self.0.chain.finalized_number = number;
+		self
+	}
+
+	/// Sets the hash of the last finalized block.
+	pub fn finalized_hash(mut self, hash: Block::Hash) -> Self {
+		self.0.chain.finalized_hash = hash;
+		self
+	}
+
+	/// Sets the number of leaves.
+	pub fn leaves(mut self, number_leaves: usize) -> Self {
+		self.0.chain.number_leaves = number_leaves;
+		self
+	}
+
+	/// Sets the memory and I/O usage of the client.
+	pub fn usage(mut self, usage: UsageInfo) -> Self {
+		self.0.usage = Some(usage);
+		self
+	}
+
+	/// Returns the built [`ClientInfo`].
+	pub fn build(self) -> ClientInfo<Block> {
+		self.0
+	}
+}
+
+impl<Block: BlockT> Default for ClientInfoBuilder<Block> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Builds a [`PoolStatus`], starting with an empty pool.
+pub struct PoolStatusBuilder(PoolStatus);
+
+impl PoolStatusBuilder {
+	/// Creates a new builder.
+	pub fn new() -> Self {
+		Self(PoolStatus { ready: 0, ready_bytes: 0, future: 0, future_bytes: 0 })
+	}
+
+	/// Sets the number of ready transactions and their total size in bytes.
+	pub fn ready(mut self, count: usize, bytes: usize) -> Self {
+		self.0.ready = count;
+		self.0.ready_bytes = bytes;
+		self
+	}
+
+	/// Sets the number of future transactions and their total size in bytes.
+	pub fn future(mut self, count: usize, bytes: usize) -> Self {
+		self.0.future = count;
+		self.0.future_bytes = bytes;
+		self
+	}
+
+	/// Returns the built [`PoolStatus`].
+	pub fn build(self) -> PoolStatus {
+		self.0
+	}
+}
+
+impl Default for PoolStatusBuilder {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Builds a [`NetworkStatus`].
+///
+/// Starts idle, without any peers, sync target or transferred bytes.
+pub struct NetworkStatusBuilder<Block: BlockT>(NetworkStatus<Block>);
+
+impl<Block: BlockT> NetworkStatusBuilder<Block> {
+	/// Creates a new builder.
+	pub fn new() -> Self {
+		Self(NetworkStatus {
+			sync_state: SyncState::Idle,
+			best_seen_block: None,
+			num_sync_peers: 0,
+			num_connected_peers: 0,
+			num_active_peers: 0,
+			total_bytes_inbound: 0,
+			total_bytes_outbound: 0,
+		})
+	}
+
+	/// Sets the sync state.
+	pub fn sync_state(mut self, sync_state: SyncState) -> Self {
+		self.0.sync_state = sync_state;
+		self
+	}
+
+	/// Sets the best block seen among the peers, i.e. the sync target.
+	pub fn best_seen_block(mut self, number: Option<NumberFor<Block>>) -> Self {
+		self.0.best_seen_block = number;
+		self
+	}
+
+	/// Sets the number of connected peers.
+	pub fn connected_peers(mut self, count: usize) -> Self {
+		self.0.num_connected_peers = count;
+		self
+	}
+
+	/// Sets the total number of bytes received and sent since the start.
+	pub fn total_bytes(mut self, inbound: u64, outbound: u64) -> Self {
+		self.0.total_bytes_inbound = inbound;
+		self.0.total_bytes_outbound = outbound;
+		self
+	}
+
+	/// Returns the built [`NetworkStatus`].
+	pub fn build(self) -> NetworkStatus<Block> {
+		self.0
+	}
+}
+
+impl<Block: BlockT> Default for NetworkStatusBuilder<Block> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
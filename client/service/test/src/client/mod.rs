@@ -1270,6 +1270,68 @@ fn state_reverted_on_reorg() {
 	assert_eq!(980, current_balance(&client));
 }
 
+#[test]
+fn reorgs_are_reported_to_prometheus() {
+	use substrate_test_runtime_client::GenesisInit;
+
+	let registry = prometheus_endpoint::Registry::new();
+	let mut client =
+		new_in_mem::<
+			_,
+			substrate_test_runtime_client::runtime::Block,
+			_,
+			substrate_test_runtime_client::runtime::RuntimeApi,
+		>(
+			substrate_test_runtime_client::new_native_executor(),
+			&substrate_test_runtime_client::GenesisParameters::default().genesis_storage(),
+			None,
+			Some(registry.clone()),
+			None,
+			Box::new(TaskExecutor::new()),
+			Default::default(),
+		)
+			.unwrap();
+
+	let genesis = client.chain_info().genesis_hash;
+	let mut import_at = |parent: Hash, nonce: u64, amount: u64| {
+		let mut builder = client.new_block_at(&BlockId::Hash(parent), Default::default(), false)
+			.unwrap();
+		builder.push_transfer(Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Bob.into(),
+			amount,
+			nonce,
+		}).unwrap();
+		let (header, extrinsics) = builder.build().unwrap().block.deconstruct();
+		let hash = header.hash();
+		let mut import = BlockImportParams::new(BlockOrigin::Own, header);
+		import.body = Some(extrinsics);
+		import.fork_choice = Some(ForkChoiceStrategy::Custom(true));
+		block_on(client.import_block(import, Default::default())).unwrap();
+		hash
+	};
+	let metric = |name: &str| registry.gather().into_iter()
+		.find(|family| family.get_name() == name)
+		.map(|family| family.get_metric()[0].clone());
+
+	// G -> A1 -> A2
+	let a1 = import_at(genesis, 0, 10);
+	let a2 = import_at(a1, 1, 10);
+	assert_eq!(metric("reorgs_total").unwrap().get_counter().get_value(), 0.0);
+
+	//   \
+	//    -> B1 (best)
+	import_at(genesis, 0, 20);
+	assert_eq!(metric("reorgs_total").unwrap().get_counter().get_value(), 1.0);
+	assert_eq!(metric("last_reorg_depth").unwrap().get_gauge().get_value(), 2.0);
+
+	// Finalizing A2 moves the best block back to the other fork.
+	ClientExt::finalize_block(&client, BlockId::Hash(a2), None).unwrap();
+	assert_eq!(client.chain_info().best_hash, a2);
+	assert_eq!(metric("reorgs_total").unwrap().get_counter().get_value(), 2.0);
+	assert_eq!(metric("last_reorg_depth").unwrap().get_gauge().get_value(), 1.0);
+}
+
 #[test]
 fn doesnt_import_blocks_that_revert_finality() {
 	sp_tracing::try_init_simple();
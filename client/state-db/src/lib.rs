@@ -452,6 +452,7 @@ impl<BlockHash: Hash + MallocSizeOf, Key: Hash + MallocSizeOf> StateDbSync<Block
 			non_canonical: MemorySize::from_bytes(malloc_size(&self.non_canonical)),
 			pruning: self.pruning.as_ref().map(|p| MemorySize::from_bytes(malloc_size(p))),
 			pinned: MemorySize::from_bytes(malloc_size(&self.pinned)),
+			pinned_blocks: self.pinned.len() as u64,
 		}
 	}
 }
@@ -673,6 +674,22 @@ mod tests {
 		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
 	}
 
+	#[test]
+	fn pinned_blocks_are_counted_once() {
+		let (_, sdb) = make_test_db(PruningMode::ArchiveCanonical);
+		let block = H256::from_low_u64_be(4);
+		assert_eq!(sdb.memory_info().pinned_blocks, 0);
+
+		sdb.pin(&block).unwrap();
+		sdb.pin(&block).unwrap();
+		assert_eq!(sdb.memory_info().pinned_blocks, 1);
+
+		sdb.unpin(&block);
+		assert_eq!(sdb.memory_info().pinned_blocks, 1);
+		sdb.unpin(&block);
+		assert_eq!(sdb.memory_info().pinned_blocks, 0);
+	}
+
 	#[test]
 	fn detects_incompatible_mode() {
 		let mut db = make_db(&[]);
@@ -38,6 +38,7 @@ use sp_runtime::transaction_validity::{
 	TransactionSource as Source,
 };
 use sp_transaction_pool::{error, PoolStatus, InPoolTransaction};
+use wasm_timer::Instant;
 
 use crate::future::{FutureTransactions, WaitingTransaction};
 use crate::ready::ReadyTransactions;
@@ -510,6 +511,11 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			future_bytes: self.future.bytes(),
 		}
 	}
+
+	/// Returns the import time of the oldest transaction in the ready queue.
+	pub fn oldest_ready_imported_at(&self) -> Option<Instant> {
+		self.ready.oldest_imported_at()
+	}
 }
 
 /// Queue limits
@@ -38,6 +38,7 @@ pub mod base_pool;
 pub mod watcher;
 
 pub use self::base_pool::Transaction;
+pub use self::listener::{EvictionReason, Evictions};
 pub use self::pool::{
 	BlockHash, ChainApi, EventStream, ExtrinsicFor, ExtrinsicHash, IsValidator, NumberFor, Options,
 	Pool, TransactionFor, ValidatedTransaction,
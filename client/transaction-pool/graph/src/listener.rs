@@ -19,6 +19,7 @@
 
 use std::{
 	collections::HashMap, hash, fmt::Debug,
+	sync::{Arc, atomic::{AtomicU64, Ordering}},
 };
 
 use linked_hash_map::LinkedHashMap;
@@ -28,10 +29,63 @@ use sp_runtime::traits;
 
 use crate::{watcher, ChainApi, ExtrinsicHash, BlockHash};
 
+/// Why a transaction was removed from the pool without being included in a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionReason {
+	/// The transaction turned out to be invalid, e.g. when it was revalidated.
+	Invalid,
+	/// The transaction was beyond its longevity period.
+	Stale,
+	/// The transaction was dropped because of the pool limits, or because it couldn't be
+	/// promoted to the ready queue anymore.
+	Dropped,
+	/// The transaction was replaced by one providing the same tags with a higher priority.
+	Replaced,
+}
+
+impl EvictionReason {
+	/// All the reasons, in the order of their counters.
+	pub const ALL: [EvictionReason; 4] = [
+		EvictionReason::Invalid,
+		EvictionReason::Stale,
+		EvictionReason::Dropped,
+		EvictionReason::Replaced,
+	];
+
+	/// Returns the name of the reason, as used for metric labels.
+	pub fn as_str(self) -> &'static str {
+		match self {
+			EvictionReason::Invalid => "invalid",
+			EvictionReason::Stale => "stale",
+			EvictionReason::Dropped => "dropped",
+			EvictionReason::Replaced => "replaced",
+		}
+	}
+}
+
+/// Number of transactions evicted from the pool since it was created, by reason.
+///
+/// Clones share the same counts.
+#[derive(Debug, Clone, Default)]
+pub struct Evictions(Arc<[AtomicU64; 4]>);
+
+impl Evictions {
+	/// Returns the number of transactions evicted because of `reason`.
+	pub fn get(&self, reason: EvictionReason) -> u64 {
+		self.0[reason as usize].load(Ordering::Relaxed)
+	}
+
+	/// Counts `count` transactions evicted because of `reason`.
+	pub(crate) fn note(&self, reason: EvictionReason, count: usize) {
+		self.0[reason as usize].fetch_add(count as u64, Ordering::Relaxed);
+	}
+}
+
 /// Extrinsic pool default listener.
 pub struct Listener<H: hash::Hash + Eq, C: ChainApi> {
 	watchers: HashMap<H, watcher::Sender<H, ExtrinsicHash<C>>>,
 	finality_watchers: LinkedHashMap<ExtrinsicHash<C>, Vec<H>>,
+	evictions: Evictions,
 }
 
 /// Maximum number of blocks awaiting finality at any time.
@@ -42,6 +96,7 @@ impl<H: hash::Hash + Eq + Debug, C: ChainApi> Default for Listener<H, C> {
 		Self {
 			watchers: Default::default(),
 			finality_watchers: Default::default(),
+			evictions: Default::default(),
 		}
 	}
 }
@@ -60,6 +115,11 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 		}
 	}
 
+	/// Returns the counts of the evicted transactions.
+	pub fn evictions(&self) -> &Evictions {
+		&self.evictions
+	}
+
 	/// Creates a new watcher for given verified extrinsic.
 	///
 	/// The watcher can be used to subscribe to life-cycle events of that extrinsic.
@@ -92,6 +152,8 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 	/// Transaction was dropped from the pool because of the limit.
 	pub fn dropped(&mut self, tx: &H, by: Option<&H>) {
 		trace!(target: "txpool", "[{:?}] Dropped (replaced with {:?})", tx, by);
+		let reason = if by.is_some() { EvictionReason::Replaced } else { EvictionReason::Dropped };
+		self.evictions.note(reason, 1);
 		self.fire(tx, |watcher| match by {
 			Some(t) => watcher.usurped(t.clone()),
 			None => watcher.dropped(),
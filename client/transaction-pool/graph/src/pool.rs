@@ -845,6 +845,50 @@ mod tests {
 		assert_matches!(err, error::Error::NoTagsProvided);
 	}
 
+	#[test]
+	fn should_count_evictions_by_reason() {
+		use crate::EvictionReason;
+
+		// given
+		let limit = Limit {
+			count: 1,
+			total_bytes: 1000,
+		};
+		let options = Options {
+			ready: limit.clone(),
+			future: limit.clone(),
+			..Default::default()
+		};
+		let pool = Pool::new(options, true.into(), TestApi::default().into());
+		let evictions = pool.validated_pool().evictions();
+		let transfer = |from, nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(from)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+
+		// when the second transaction exceeds the limit
+		block_on(pool.submit_one(&BlockId::Number(0), SOURCE, transfer(1, 0))).unwrap();
+		let hash = block_on(pool.submit_one(&BlockId::Number(1), SOURCE, transfer(2, 1))).unwrap();
+		assert_eq!(evictions.get(EvictionReason::Dropped), 1);
+
+		// when it's found invalid
+		pool.validated_pool().remove_invalid(&[hash]);
+		assert_eq!(evictions.get(EvictionReason::Invalid), 1);
+
+		// when a transaction outlives its longevity
+		block_on(pool.submit_one(&BlockId::Number(1), SOURCE, transfer(3, 1))).unwrap();
+		pool.validated_pool().clear_stale(&BlockId::Number(10)).unwrap();
+
+		// then
+		assert_eq!(pool.validated_pool().status().ready, 0);
+		assert_eq!(evictions.get(EvictionReason::Stale), 1);
+		assert_eq!(evictions.get(EvictionReason::Dropped), 1);
+		assert_eq!(evictions.get(EvictionReason::Invalid), 1);
+		assert_eq!(evictions.get(EvictionReason::Replaced), 0);
+	}
+
 	mod listener {
 		use super::*;
 
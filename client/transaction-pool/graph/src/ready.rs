@@ -30,6 +30,7 @@ use sp_runtime::transaction_validity::{
 	TransactionTag as Tag,
 };
 use sp_transaction_pool::error;
+use wasm_timer::Instant;
 
 use crate::{
 	base_pool::Transaction,
@@ -46,6 +47,9 @@ pub struct TransactionRef<Hash, Ex> {
 	pub transaction: Arc<Transaction<Hash, Ex>>,
 	/// Unique id when transaction was inserted into the pool.
 	pub insertion_id: u64,
+	/// Time of import to the pool.
+	#[ignore_malloc_size_of = "not allocated on the heap"]
+	pub imported_at: Instant,
 }
 
 impl<Hash, Ex> Clone for TransactionRef<Hash, Ex> {
@@ -53,6 +57,7 @@ impl<Hash, Ex> Clone for TransactionRef<Hash, Ex> {
 		Self {
 			transaction: self.transaction.clone(),
 			insertion_id: self.insertion_id,
+			imported_at: self.imported_at,
 		}
 	}
 }
@@ -179,6 +184,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 
 		self.insertion_id += 1;
 		let insertion_id = self.insertion_id;
+		let imported_at = tx.imported_at;
 		let hash = tx.transaction.hash.clone();
 		let transaction = tx.transaction;
 
@@ -209,6 +215,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 
 		let transaction = TransactionRef {
 			insertion_id,
+			imported_at,
 			transaction
 		};
 
@@ -481,6 +488,11 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 	pub fn bytes(&self) -> usize {
 		self.ready.bytes()
 	}
+
+	/// Returns the import time of the transaction that has been waiting in this queue the longest.
+	pub fn oldest_imported_at(&self) -> Option<Instant> {
+		self.ready.read().values().map(|tx| tx.transaction.imported_at).min()
+	}
 }
 
 /// Iterator of ready transactions ordered by priority.
@@ -703,6 +715,31 @@ mod tests {
 		assert!(parity_util_mem::malloc_size(&ready) > 200);
 	}
 
+	#[test]
+	fn should_return_the_import_time_of_the_oldest_transaction() {
+		// given
+		let mut ready = ReadyTransactions::default();
+		assert_eq!(ready.oldest_imported_at(), None);
+		let mut tx1 = tx(1);
+		tx1.requires.clear();
+		tx1.provides = vec![vec![1]];
+		let mut tx2 = tx(2);
+		tx2.requires.clear();
+		tx2.provides = vec![vec![2]];
+		let imported_at = Instant::now() - std::time::Duration::from_secs(60);
+
+		// when
+		let mut old = WaitingTransaction::new(tx1, ready.provided_tags(), &[]);
+		old.imported_at = imported_at;
+		ready.import(old).unwrap();
+		import(&mut ready, tx2).unwrap();
+
+		// then
+		assert_eq!(ready.oldest_imported_at(), Some(imported_at));
+		ready.remove_subtree(&[1]);
+		assert!(ready.oldest_imported_at().unwrap() > imported_at);
+	}
+
 	#[test]
 	fn should_order_refs() {
 		let mut id = 1;
@@ -717,25 +754,31 @@ mod tests {
 		assert!(TransactionRef {
 			transaction: Arc::new(with_priority(3, 3)),
 			insertion_id: 1,
+			imported_at: Instant::now(),
 		} > TransactionRef {
 			transaction: Arc::new(with_priority(2, 3)),
 			insertion_id: 2,
+			imported_at: Instant::now(),
 		});
 		// lower validity = better
 		assert!(TransactionRef {
 			transaction: Arc::new(with_priority(3, 2)),
 			insertion_id: 1,
+			imported_at: Instant::now(),
 		} > TransactionRef {
 			transaction: Arc::new(with_priority(3, 3)),
 			insertion_id: 2,
+			imported_at: Instant::now(),
 		});
 		// lower insertion_id = better
 		assert!(TransactionRef {
 			transaction: Arc::new(with_priority(3, 3)),
 			insertion_id: 1,
+			imported_at: Instant::now(),
 		} > TransactionRef {
 			transaction: Arc::new(with_priority(3, 3)),
 			insertion_id: 2,
+			imported_at: Instant::now(),
 		});
 	}
 }
@@ -35,7 +35,7 @@ use futures::channel::mpsc::{channel, Sender};
 use retain_mut::RetainMut;
 
 use crate::base_pool::{self as base, PruneStatus};
-use crate::listener::Listener;
+use crate::listener::{EvictionReason, Evictions, Listener};
 use crate::rotator::PoolRotator;
 use crate::watcher::Watcher;
 use crate::pool::{
@@ -415,7 +415,10 @@ impl<B: ChainApi> ValidatedPool<B> {
 					Status::Future => listener.future(&hash),
 					Status::Ready => listener.ready(&hash, None),
 					Status::Dropped => listener.dropped(&hash, None),
-					Status::Failed => listener.invalid(&hash),
+					Status::Failed => {
+						listener.evictions().note(EvictionReason::Invalid, 1);
+						listener.invalid(&hash);
+					},
 				}
 			}
 		}
@@ -539,8 +542,8 @@ impl<B: ChainApi> ValidatedPool<B> {
 			hashes
 		};
 		// removing old transactions
-		self.remove_invalid(&to_remove);
-		self.remove_invalid(&futures_to_remove);
+		self.evict(&to_remove, EvictionReason::Stale);
+		self.evict(&futures_to_remove, EvictionReason::Stale);
 		// clear banned transactions timeouts
 		self.rotator.clear_timeouts(&now);
 
@@ -553,6 +556,11 @@ impl<B: ChainApi> ValidatedPool<B> {
 		&self.rotator
 	}
 
+	/// Returns the counts of the transactions evicted from the pool.
+	pub fn evictions(&self) -> Evictions {
+		self.listener.read().evictions().clone()
+	}
+
 	/// Get api reference.
 	pub fn api(&self) -> &B {
 		&self.api
@@ -585,6 +593,11 @@ impl<B: ChainApi> ValidatedPool<B> {
 	/// Note this is not the case for the dependent transactions - those may
 	/// still be valid so we want to be able to re-import them.
 	pub fn remove_invalid(&self, hashes: &[ExtrinsicHash<B>]) -> Vec<TransactionFor<B>> {
+		self.evict(hashes, EvictionReason::Invalid)
+	}
+
+	/// Like [`Self::remove_invalid`], counting the removed transactions as evicted for `reason`.
+	fn evict(&self, hashes: &[ExtrinsicHash<B>], reason: EvictionReason) -> Vec<TransactionFor<B>> {
 		// early exit in case there is no invalid transactions.
 		if hashes.is_empty() {
 			return vec![];
@@ -600,6 +613,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 		log::debug!(target: "txpool", "Removed invalid transactions: {:?}", invalid);
 
 		let mut listener = self.listener.write();
+		listener.evictions().note(reason, invalid.len());
 		for tx in &invalid {
 			listener.invalid(&tx.hash);
 		}
@@ -624,6 +638,11 @@ impl<B: ChainApi> ValidatedPool<B> {
 		self.pool.read().status()
 	}
 
+	/// Returns the import time of the transaction waiting the longest in the ready queue.
+	pub fn oldest_ready_imported_at(&self) -> Option<Instant> {
+		self.pool.read().oldest_ready_imported_at()
+	}
+
 	/// Notify all watchers that transactions in the block with hash have been finalized
 	pub async fn on_block_finalized(&self, block_hash: BlockHash<B>) -> Result<(), B::Error> {
 		log::trace!(target: "txpool", "Attempting to notify watchers of finalization for {}", block_hash);
@@ -647,6 +666,7 @@ fn fire_events<H, B, Ex>(
 	match *imported {
 		base::Imported::Ready { ref promoted, ref failed, ref removed, ref hash } => {
 			listener.ready(hash, None);
+			listener.evictions().note(EvictionReason::Invalid, failed.len());
 			failed.into_iter().for_each(|f| listener.invalid(f));
 			removed.into_iter().for_each(|r| listener.dropped(&r.hash, Some(hash)));
 			promoted.into_iter().for_each(|p| listener.ready(p, None));
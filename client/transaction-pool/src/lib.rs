@@ -211,6 +211,16 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 			spawner.spawn("txpool-background", background_task);
 		}
 
+		let oldest_ready = {
+			let pool = Arc::downgrade(&pool);
+			Arc::new(move || pool.upgrade()?.validated_pool().oldest_ready_imported_at())
+		};
+		let metrics = PrometheusMetrics::new(
+			prometheus,
+			pool.validated_pool().evictions(),
+			oldest_ready,
+		);
+
 		Self {
 			api: pool_api,
 			pool,
@@ -222,7 +232,7 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 				}
 			)),
 			ready_poll: Arc::new(Mutex::new(ReadyPoll::new(best_block_number))),
-			metrics: PrometheusMetrics::new(prometheus),
+			metrics,
 		}
 	}
 
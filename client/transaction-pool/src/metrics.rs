@@ -20,16 +20,28 @@
 
 use std::sync::Arc;
 
-use prometheus_endpoint::{register, Counter, PrometheusError, Registry, U64};
+use prometheus_endpoint::{
+	register, Counter, MetricSource, Opts, PrometheusError, Registry, SourcedCounter,
+	SourcedGauge, U64,
+};
+use sc_transaction_graph::{EvictionReason, Evictions};
+use wasm_timer::Instant;
+
+/// Returns the import time of the transaction waiting the longest in the ready queue.
+pub type OldestReady = Arc<dyn Fn() -> Option<Instant> + Send + Sync>;
 
 #[derive(Clone, Default)]
 pub struct MetricsLink(Arc<Option<Metrics>>);
 
 impl MetricsLink {
-	pub fn new(registry: Option<&Registry>) -> Self {
+	pub fn new(
+		registry: Option<&Registry>,
+		evictions: Evictions,
+		oldest_ready: OldestReady,
+	) -> Self {
 		Self(Arc::new(
 			registry.and_then(|registry|
-				Metrics::register(registry)
+				Metrics::register(registry, evictions, oldest_ready)
 					.map_err(|err| { log::warn!("Failed to register prometheus metrics: {}", err); })
 					.ok()
 			)
@@ -52,7 +64,34 @@ pub struct Metrics {
 }
 
 impl Metrics {
-	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+	pub fn register(
+		registry: &Registry,
+		evictions: Evictions,
+		oldest_ready: OldestReady,
+	) -> Result<Self, PrometheusError> {
+		register(
+			SourcedCounter::new(
+				&Opts::new(
+					"txpool_evictions_total",
+					"Total number of transactions removed from the pool without being included \
+					in a block, by reason",
+				).variable_label("reason"),
+				EvictionsSource(evictions),
+			)?,
+			registry,
+		)?;
+		register(
+			SourcedGauge::new(
+				&Opts::new(
+					"txpool_oldest_ready_seconds",
+					"Number of seconds the oldest transaction of the ready queue has been \
+					waiting in the pool",
+				),
+				OldestReadySource(oldest_ready),
+			)?,
+			registry,
+		)?;
+
 		Ok(Self {
 			submitted_transactions: register(
 				Counter::new(
@@ -86,6 +125,33 @@ impl Metrics {
 	}
 }
 
+/// The source of the `txpool_evictions_total` counter.
+#[derive(Clone)]
+struct EvictionsSource(Evictions);
+
+impl MetricSource for EvictionsSource {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		for reason in EvictionReason::ALL.iter() {
+			set(&[reason.as_str()], self.0.get(*reason));
+		}
+	}
+}
+
+/// The source of the `txpool_oldest_ready_seconds` gauge.
+#[derive(Clone)]
+struct OldestReadySource(OldestReady);
+
+impl MetricSource for OldestReadySource {
+	type N = f64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		let age = (self.0)().map_or(0.0, |imported_at| imported_at.elapsed().as_secs_f64());
+		set(&[], age);
+	}
+}
+
 /// Transaction pool api Prometheus metrics.
 pub struct ApiMetrics {
 	pub validations_scheduled: Counter<U64>,
@@ -127,3 +193,32 @@ impl ApiMetricsExt for Option<Arc<ApiMetrics>> {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	fn oldest_ready_seconds(registry: &Registry) -> Option<f64> {
+		registry.gather().into_iter()
+			.find(|family| family.get_name() == "txpool_oldest_ready_seconds")
+			.map(|family| family.get_metric()[0].get_gauge().get_value())
+	}
+
+	#[test]
+	fn oldest_ready_transaction_age_is_reported() {
+		let registry = Registry::new();
+		let imported_at = Instant::now() - Duration::from_secs(60);
+		MetricsLink::new(Some(&registry), Default::default(), Arc::new(move || Some(imported_at)));
+
+		assert!(oldest_ready_seconds(&registry).unwrap() >= 60.0);
+	}
+
+	#[test]
+	fn oldest_ready_transaction_age_is_zero_without_ready_transactions() {
+		let registry = Registry::new();
+		MetricsLink::new(Some(&registry), Default::default(), Arc::new(|| None));
+
+		assert_eq!(oldest_ready_seconds(&registry), Some(0.0));
+	}
+}
@@ -39,6 +39,7 @@ use crate::{
 	metrics::Metrics,
 };
 pub use basic_queue::BasicQueue;
+pub use crate::metrics::ImportTimeBuckets;
 
 /// A commonly-used Import Queue type.
 ///
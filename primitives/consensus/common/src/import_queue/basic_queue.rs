@@ -30,7 +30,7 @@ use crate::{
 		IncomingBlock, import_single_block_metered,
 		buffered_link::{self, BufferedLinkSender, BufferedLinkReceiver},
 	},
-	metrics::Metrics,
+	metrics::{ImportTimeBuckets, Metrics},
 };
 
 /// Interface to a basic block import queue that is importing blocks sequentially in a separate
@@ -42,6 +42,7 @@ pub struct BasicQueue<B: BlockT, Transaction> {
 	block_import_sender: TracingUnboundedSender<worker_messages::ImportBlocks<B>>,
 	/// Results coming from the worker task.
 	result_port: BufferedLinkReceiver<B>,
+	metrics: Option<Metrics>,
 	_phantom: PhantomData<Transaction>,
 }
 
@@ -64,11 +65,30 @@ impl<B: BlockT, Transaction: Send + 'static> BasicQueue<B, Transaction> {
 		justification_import: Option<BoxJustificationImport<B>>,
 		spawner: &impl sp_core::traits::SpawnEssentialNamed,
 		prometheus_registry: Option<&Registry>,
+	) -> Self {
+		Self::with_import_time_buckets(
+			verifier,
+			block_import,
+			justification_import,
+			spawner,
+			prometheus_registry,
+			&ImportTimeBuckets::default(),
+		)
+	}
+
+	/// Like [`BasicQueue::new`], but times the block imports with the given histogram buckets.
+	pub fn with_import_time_buckets<V: 'static + Verifier<B>>(
+		verifier: V,
+		block_import: BoxBlockImport<B, Transaction>,
+		justification_import: Option<BoxJustificationImport<B>>,
+		spawner: &impl sp_core::traits::SpawnEssentialNamed,
+		prometheus_registry: Option<&Registry>,
+		import_time_buckets: &ImportTimeBuckets,
 	) -> Self {
 		let (result_sender, result_port) = buffered_link::buffered_link();
 
 		let metrics = prometheus_registry.and_then(|r| {
-			Metrics::register(r)
+			Metrics::register_with_buckets(r, import_time_buckets)
 				.map_err(|err| {
 					log::warn!("Failed to register Prometheus metrics: {}", err);
 				})
@@ -80,7 +100,7 @@ impl<B: BlockT, Transaction: Send + 'static> BasicQueue<B, Transaction> {
 			verifier,
 			block_import,
 			justification_import,
-			metrics,
+			metrics.clone(),
 		);
 
 		spawner.spawn_essential_blocking("basic-block-import-worker", future.boxed());
@@ -89,6 +109,7 @@ impl<B: BlockT, Transaction: Send + 'static> BasicQueue<B, Transaction> {
 			justification_sender,
 			block_import_sender,
 			result_port,
+			metrics,
 			_phantom: PhantomData,
 		}
 	}
@@ -101,10 +122,20 @@ impl<B: BlockT, Transaction: Send> ImportQueue<B> for BasicQueue<B, Transaction>
 		}
 
 		trace!(target: "sync", "Scheduling {} blocks for import", blocks.len());
+		// Counted before sending, so that the worker never takes out more blocks than were counted.
+		let count = blocks.len() as u64;
+		if let Some(metrics) = self.metrics.as_ref() {
+			metrics.import_queue_length.add(count);
+		}
+
 		let res =
 			self.block_import_sender.unbounded_send(worker_messages::ImportBlocks(origin, blocks));
 
 		if res.is_err() {
+			if let Some(metrics) = self.metrics.as_ref() {
+				metrics.import_queue_length.sub(count);
+			}
+
 			log::error!(
 				target: "sync",
 				"import_blocks: Background import task is no longer alive"
@@ -228,8 +259,10 @@ impl<B: BlockT> BlockImportWorker<B> {
 		}
 
 		let delay_between_blocks = Duration::default();
+		let reset_queue_length = ResetQueueLength(worker.metrics.clone());
 
 		let future = async move {
+			let _reset_queue_length = reset_queue_length;
 			let block_import_process = block_import_process(
 				block_import,
 				verifier,
@@ -309,6 +342,19 @@ impl<B: BlockT> BlockImportWorker<B> {
 	}
 }
 
+/// Resets the import queue length when dropped along with the worker.
+///
+/// The blocks left in the channel once the worker stopped are never imported.
+struct ResetQueueLength(Option<Metrics>);
+
+impl Drop for ResetQueueLength {
+	fn drop(&mut self) {
+		if let Some(metrics) = self.0.as_ref() {
+			metrics.import_queue_length.set(0);
+		}
+	}
+}
+
 /// Result of [`import_many_blocks`].
 struct ImportManyBlocksResult<B: BlockT> {
 	/// The number of blocks imported successfully.
@@ -376,6 +422,7 @@ async fn import_many_blocks<B: BlockT, V: Verifier<B>, Transaction: Send + 'stat
 
 		if let Some(metrics) = metrics.as_ref() {
 			metrics.report_import::<B>(&import_result);
+			metrics.import_queue_length.dec();
 		}
 
 		if import_result.is_ok() {
@@ -611,4 +658,70 @@ mod tests {
 			]
 		);
 	}
+
+	#[test]
+	fn imported_blocks_leave_the_queue_length() {
+		let metrics = Metrics::register(&Registry::new()).unwrap();
+		let block = |n| {
+			let header = Header {
+				parent_hash: Hash::random(),
+				number: n,
+				extrinsics_root: Hash::random(),
+				state_root: Default::default(),
+				digest: Default::default(),
+			};
+			IncomingBlock {
+				hash: header.hash(),
+				header: Some(header),
+				body: None,
+				justifications: None,
+				origin: None,
+				allow_missing_state: false,
+				import_existing: false,
+			}
+		};
+
+		metrics.import_queue_length.add(3);
+		let mut block_import: BoxBlockImport<Block, Extrinsic> = Box::new(());
+		let result = block_on(import_many_blocks(
+			&mut block_import,
+			BlockOrigin::Own,
+			vec![block(1), block(2)],
+			&mut (),
+			Duration::default(),
+			Some(metrics.clone()),
+		));
+
+		assert_eq!(result.imported, 2);
+		assert_eq!(metrics.import_queue_length.get(), 1);
+	}
+
+	#[test]
+	fn queue_length_is_reset_when_the_worker_stops() {
+		let metrics = Metrics::register(&Registry::new()).unwrap();
+		let new_worker = || {
+			let (result_sender, result_port) = buffered_link::buffered_link();
+			let (worker, finality_sender, block_import_sender) = BlockImportWorker::new(
+				result_sender,
+				(),
+				Box::new(()),
+				None,
+				Some(metrics.clone()),
+			);
+			(worker, result_port, finality_sender, block_import_sender)
+		};
+
+		// The import channel is closed with blocks left in it.
+		let (worker, _result_port, _finality_sender, block_import_sender) = new_worker();
+		metrics.import_queue_length.add(3);
+		drop(block_import_sender);
+		block_on(worker);
+		assert_eq!(metrics.import_queue_length.get(), 0);
+
+		// The worker is dropped on shutdown.
+		let (worker, _result_port, _finality_sender, _block_import_sender) = new_worker();
+		metrics.import_queue_length.add(3);
+		drop(worker);
+		assert_eq!(metrics.import_queue_length.get(), 0);
+	}
 }
@@ -18,17 +18,61 @@
 //! Metering tools for consensus
 
 use prometheus_endpoint::{
-	register, U64, Registry, PrometheusError, Opts, CounterVec, Histogram, HistogramVec, HistogramOpts
+	register, U64, Registry, PrometheusError, Opts, CounterVec, Gauge, Histogram, HistogramVec,
+	HistogramOpts, exponential_buckets,
 };
 
 use sp_runtime::traits::{Block as BlockT, NumberFor};
 
 use crate::import_queue::{BlockImportResult, BlockImportError};
 
+/// Upper bounds, in seconds, of the buckets of the histograms timing block imports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportTimeBuckets(Vec<f64>);
+
+impl ImportTimeBuckets {
+	/// Uses `bounds` as upper bounds of the buckets.
+	///
+	/// Fails unless the bounds are finite and strictly increasing, and there is at least one.
+	pub fn new(bounds: Vec<f64>) -> Result<Self, PrometheusError> {
+		if bounds.is_empty() {
+			return Err(PrometheusError::Msg("Import time buckets can't be empty".into()))
+		}
+		if bounds.iter().any(|bound| !bound.is_finite()) {
+			return Err(PrometheusError::Msg(format!(
+				"Import time buckets must be finite, got {:?}",
+				bounds,
+			)))
+		}
+		if bounds.windows(2).any(|pair| pair[0] >= pair[1]) {
+			return Err(PrometheusError::Msg(format!(
+				"Import time buckets must be strictly increasing, got {:?}",
+				bounds,
+			)))
+		}
+		Ok(Self(bounds))
+	}
+
+	/// Returns the upper bounds of the buckets.
+	pub fn bounds(&self) -> &[f64] {
+		&self.0
+	}
+}
+
+impl Default for ImportTimeBuckets {
+	/// Blocks can take anything from a millisecond to tens of seconds to import, so the default
+	/// linear-ish buckets of Prometheus (5ms to 10s) cut off both ends. These go from 1ms to ~33s.
+	fn default() -> Self {
+		Self(exponential_buckets(0.001, 2.0, 16)
+			.expect("start, factor and count are valid constants; qed"))
+	}
+}
+
 /// Generic Prometheus metrics for common consensus functionality.
 #[derive(Clone)]
 pub(crate) struct Metrics {
 	pub import_queue_processed: CounterVec<U64>,
+	pub import_queue_length: Gauge<U64>,
 	pub block_verification_time: HistogramVec,
 	pub block_verification_and_import_time: Histogram,
 	pub justification_import_time: Histogram,
@@ -36,20 +80,33 @@ pub(crate) struct Metrics {
 
 impl Metrics {
 	pub(crate) fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Self::register_with_buckets(registry, &ImportTimeBuckets::default())
+	}
+
+	pub(crate) fn register_with_buckets(
+		registry: &Registry,
+		import_time_buckets: &ImportTimeBuckets,
+	) -> Result<Self, PrometheusError> {
+		let import_time_buckets = import_time_buckets.bounds().to_vec();
+
 		Ok(Self {
 			import_queue_processed: register(
 				CounterVec::new(
 					Opts::new("import_queue_processed_total", "Blocks processed by import queue"),
-					&["result"] // 'success or failure
+					&["result"] // 'success', 'known' or the kind of failure
 				)?,
 				registry,
 			)?,
+			import_queue_length: register(
+				Gauge::new("import_queue_length", "Number of blocks waiting in the import queue")?,
+				registry,
+			)?,
 			block_verification_time: register(
 				HistogramVec::new(
 					HistogramOpts::new(
 						"block_verification_time",
 						"Time taken to verify blocks",
-					),
+					).buckets(import_time_buckets.clone()),
 					&["result"],
 				)?,
 				registry,
@@ -59,7 +116,7 @@ impl Metrics {
 					HistogramOpts::new(
 						"block_verification_and_import_time",
 						"Time taken to verify and import blocks",
-					),
+					).buckets(import_time_buckets),
 				)?,
 				registry,
 			)?,
@@ -80,7 +137,8 @@ impl Metrics {
 		result: &Result<BlockImportResult<NumberFor<B>>, BlockImportError>,
 	) {
 		let label = match result {
-			Ok(_) => "success",
+			Ok(BlockImportResult::ImportedUnknown(..)) => "success",
+			Ok(BlockImportResult::ImportedKnown(..)) => "known",
 			Err(BlockImportError::IncompleteHeader(_)) => "incomplete_header",
 			Err(BlockImportError::VerificationFailed(_,_)) => "verification_failed",
 			Err(BlockImportError::BadBlock(_)) => "bad_block",
@@ -105,3 +163,64 @@ impl Metrics {
 		self.block_verification_and_import_time.observe(time.as_secs_f64());
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_test_primitives::Block;
+
+	#[test]
+	fn import_time_buckets_cover_fast_and_slow_imports() {
+		let registry = Registry::new();
+		let metrics = Metrics::register(&registry).unwrap();
+		metrics.report_verification_and_import(std::time::Duration::from_millis(1));
+
+		let family = registry.gather().into_iter()
+			.find(|family| family.get_name() == "block_verification_and_import_time")
+			.unwrap();
+		let buckets = family.get_metric()[0].get_histogram().get_bucket();
+		assert_eq!(buckets.first().unwrap().get_upper_bound(), 0.001);
+		assert!(buckets.last().unwrap().get_upper_bound() >= 30.0);
+		assert!(buckets.windows(2).all(|b| b[0].get_upper_bound() < b[1].get_upper_bound()));
+	}
+
+	#[test]
+	fn invalid_import_time_buckets_are_rejected() {
+		assert!(ImportTimeBuckets::new(vec![]).is_err());
+		assert!(ImportTimeBuckets::new(vec![0.1, 0.01]).is_err());
+		assert!(ImportTimeBuckets::new(vec![0.1, 0.1, 1.0]).is_err());
+		assert!(ImportTimeBuckets::new(vec![0.1, f64::NAN]).is_err());
+		assert!(ImportTimeBuckets::new(vec![0.1, f64::INFINITY]).is_err());
+
+		let buckets = ImportTimeBuckets::new(vec![0.5, 1.0, 60.0]).unwrap();
+		let registry = Registry::new();
+		let metrics = Metrics::register_with_buckets(&registry, &buckets).unwrap();
+		metrics.report_verification_and_import(std::time::Duration::from_secs(2));
+
+		let family = registry.gather().into_iter()
+			.find(|family| family.get_name() == "block_verification_and_import_time")
+			.unwrap();
+		let bounds: Vec<_> = family.get_metric()[0].get_histogram().get_bucket().iter()
+			.map(|bucket| bucket.get_upper_bound())
+			.collect();
+		assert_eq!(bounds, vec![0.5, 1.0, 60.0]);
+	}
+
+	#[test]
+	fn import_results_are_split_by_outcome() {
+		let metrics = Metrics::register(&Registry::new()).unwrap();
+
+		let imported = |number| {
+			Ok(BlockImportResult::ImportedUnknown(number, Default::default(), None))
+		};
+		metrics.report_import::<Block>(&imported(1));
+		metrics.report_import::<Block>(&imported(2));
+		metrics.report_import::<Block>(&Ok(BlockImportResult::ImportedKnown(2, None)));
+		metrics.report_import::<Block>(&Err(BlockImportError::UnknownParent));
+
+		let processed = |result| metrics.import_queue_processed.with_label_values(&[result]).get();
+		assert_eq!(processed("success"), 2);
+		assert_eq!(processed("known"), 1);
+		assert_eq!(processed("unknown_parent"), 1);
+	}
+}
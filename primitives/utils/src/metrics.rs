@@ -20,11 +20,13 @@
 use lazy_static::lazy_static;
 use prometheus::{
 	Registry, Error as PrometheusError,
-	core::{ AtomicU64, GenericGauge, GenericCounter },
+	core::{ AtomicU64, Collector, GenericGauge, GenericCounter },
 };
 
 #[cfg(feature = "metered")]
 use prometheus::{core::GenericCounterVec, Opts};
+#[cfg(feature = "metered")]
+use std::collections::BTreeMap;
 
 
 lazy_static! {
@@ -47,13 +49,94 @@ lazy_static! {
 }
 
 
+/// Returns the statics that [`register_globals`] registers.
+pub fn globals() -> Vec<Box<dyn Collector>> {
+	// Only extended with the `metered` feature.
+	#[allow(unused_mut)]
+	let mut globals: Vec<Box<dyn Collector>> = vec![
+		Box::new(TOKIO_THREADS_ALIVE.clone()),
+		Box::new(TOKIO_THREADS_TOTAL.clone()),
+	];
+
+	#[cfg(feature = "metered")]
+	globals.push(Box::new(UNBOUNDED_CHANNELS_COUNTER.clone()));
+
+	globals
+}
+
 /// Register the statics to report to registry
 pub fn register_globals(registry: &Registry) -> Result<(), PrometheusError> {
-	registry.register(Box::new(TOKIO_THREADS_ALIVE.clone()))?;
-	registry.register(Box::new(TOKIO_THREADS_TOTAL.clone()))?;
+	for global in globals() {
+		registry.register(global)?;
+	}
+
+	Ok(())
+}
 
+/// Returns the number of items currently queued in the `tracing_unbounded` channels,
+/// summed up per channel name and sorted by that name.
+///
+/// Always empty if the `metered` feature is disabled.
+pub fn unbounded_channels_depth() -> Vec<(String, u64)> {
 	#[cfg(feature = "metered")]
-	registry.register(Box::new(UNBOUNDED_CHANNELS_COUNTER.clone()))?;
+	{
+		// channel name -> (sent, received or dropped)
+		let mut flows = BTreeMap::<String, (u64, u64)>::new();
+		for family in UNBOUNDED_CHANNELS_COUNTER.collect() {
+			for metric in family.get_metric() {
+				let label = |name: &str| metric.get_label().iter()
+					.find(|pair| pair.get_name() == name)
+					.map(|pair| pair.get_value().to_owned());
+				let (entity, action) = match (label("entity"), label("action")) {
+					(Some(entity), Some(action)) => (entity, action),
+					_ => continue,
+				};
+				let value = metric.get_counter().get_value() as u64;
+				let flow = flows.entry(entity).or_default();
+				match action.as_str() {
+					"send" => flow.0 += value,
+					_ => flow.1 += value,
+				}
+			}
+		}
 
-	Ok(())
+		flows.into_iter()
+			.map(|(entity, (sent, taken))| (entity, sent.saturating_sub(taken)))
+			.collect()
+	}
+
+	#[cfg(not(feature = "metered"))]
+	Vec::new()
+}
+
+#[cfg(all(test, feature = "metered"))]
+mod tests {
+	use super::*;
+	use crate::mpsc::tracing_unbounded;
+	use futures::{executor::block_on, StreamExt};
+
+	fn depth(entity: &str) -> Option<u64> {
+		unbounded_channels_depth().into_iter()
+			.find(|(name, _)| name == entity)
+			.map(|(_, depth)| depth)
+	}
+
+	#[test]
+	fn channels_depth_follows_the_channels() {
+		let (sender, mut receiver) = tracing_unbounded("mpsc_depth_test");
+		assert_eq!(depth("mpsc_depth_test"), None);
+
+		for item in 0..3 {
+			sender.unbounded_send(item).unwrap();
+		}
+		assert_eq!(depth("mpsc_depth_test"), Some(3));
+
+		assert_eq!(block_on(receiver.next()), Some(0));
+		assert_eq!(receiver.try_next().unwrap(), Some(1));
+		assert_eq!(depth("mpsc_depth_test"), Some(1));
+
+		// The items left are discounted when the receiver is dropped.
+		drop(receiver);
+		assert_eq!(depth("mpsc_depth_test"), Some(0));
+	}
 }
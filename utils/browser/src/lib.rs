@@ -75,6 +75,7 @@ where
 			async {}
 		}).into(),
 		telemetry_external_transport: Some(transport),
+		telemetry_fields: Default::default(),
 		role: Role::Light,
 		database: {
 			info!("Opening Indexed DB database '{}'...", name);
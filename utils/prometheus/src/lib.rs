@@ -15,7 +15,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use futures_util::{FutureExt, future::Future};
+use futures_util::future::Future;
 pub use prometheus::{
 	self,
 	Registry, Error as PrometheusError, Opts,
@@ -40,6 +40,8 @@ pub use sourced::{SourcedCounter, SourcedGauge, MetricSource, SourcedMetric};
 pub use unknown_os::init_prometheus;
 #[cfg(not(target_os = "unknown"))]
 pub use known_os::init_prometheus;
+#[cfg(unix)]
+pub use known_os::init_prometheus_unix;
 
 pub fn register<T: Clone + Collector + 'static>(metric: T, registry: &Registry) -> Result<T, PrometheusError> {
 	registry.register(Box::new(metric.clone()))?;
@@ -73,7 +75,10 @@ mod known_os {
 		/// i/o error.
 		Io(std::io::Error),
 		#[display(fmt = "Prometheus port {} already in use.", _0)]
-		PortInUse(SocketAddr)
+		PortInUse(SocketAddr),
+		#[cfg(unix)]
+		#[display(fmt = "Prometheus socket {} already in use.", "_0.display()")]
+		SocketInUse(std::path::PathBuf),
 	}
 
 	impl std::error::Error for Error {
@@ -82,7 +87,9 @@ mod known_os {
 				Error::Hyper(error) => Some(error),
 				Error::Http(error) => Some(error),
 				Error::Io(error) => Some(error),
-				Error::PortInUse(_) => None
+				Error::PortInUse(_) => None,
+				#[cfg(unix)]
+				Error::SocketInUse(_) => None,
 			}
 		}
 	}
@@ -129,6 +136,41 @@ mod known_os {
 
 		log::info!("〽️ Prometheus server started at {}", prometheus_addr);
 
+		serve(Incoming(listener.incoming()), registry).await
+	}
+
+	/// Like [`init_prometheus`], but serves metrics on a Unix domain socket at `path` instead of
+	/// a TCP port, so that they are only reachable from the local machine.
+	///
+	/// A socket left at `path` by a server that isn't running anymore is replaced. The socket is
+	/// removed when the returned future is dropped.
+	#[cfg(unix)]
+	pub async fn init_prometheus_unix(
+		path: std::path::PathBuf,
+		registry: Registry,
+	) -> Result<(), Error> {
+		use networking::UnixIncoming;
+		remove_stale_socket(&path).await?;
+		let listener = async_std::os::unix::net::UnixListener::bind(&path)
+			.await
+			.map_err(|err| match err.kind() {
+				std::io::ErrorKind::AddrInUse => Error::SocketInUse(path.clone()),
+				_ => Error::Io(err),
+			})?;
+		let _socket = RemoveOnDrop(path.clone());
+
+		log::info!("〽️ Prometheus server started at {}", path.display());
+
+		serve(UnixIncoming(listener.incoming()), registry).await
+	}
+
+	/// Serves the metrics of `registry` to the connections accepted from `incoming`.
+	async fn serve<I>(incoming: I, registry: Registry) -> Result<(), Error>
+	where
+		I: hyper::server::accept::Accept,
+		I::Conn: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+		I::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+	{
 		let service = make_service_fn(move |_| {
 			let registry = registry.clone();
 
@@ -139,13 +181,43 @@ mod known_os {
 			}
 		});
 
-		let server = Server::builder(Incoming(listener.incoming()))
+		Server::builder(incoming)
 			.executor(Executor)
 			.serve(service)
-			.boxed();
+			.await
+			.map_err(Into::into)
+	}
+
+	/// Removes the socket at `path` if nothing listens on it anymore, e.g. after a crash.
+	///
+	/// Anything else found at `path` is left for binding to report.
+	#[cfg(unix)]
+	async fn remove_stale_socket(path: &std::path::Path) -> Result<(), Error> {
+		use std::os::unix::fs::FileTypeExt;
 
-		let result = server.await.map_err(Into::into);
+		match async_std::fs::symlink_metadata(path).await {
+			Ok(metadata) if metadata.file_type().is_socket() => (),
+			_ => return Ok(()),
+		}
 
-		result
+		match async_std::os::unix::net::UnixStream::connect(path).await {
+			Ok(_) => Err(Error::SocketInUse(path.into())),
+			Err(err) if err.kind() == std::io::ErrorKind::ConnectionRefused =>
+				async_std::fs::remove_file(path).await.map_err(Error::Io),
+			Err(err) => Err(Error::Io(err)),
+		}
+	}
+
+	/// Removes the socket file at the given path when dropped.
+	#[cfg(unix)]
+	struct RemoveOnDrop(std::path::PathBuf);
+
+	#[cfg(unix)]
+	impl Drop for RemoveOnDrop {
+		fn drop(&mut self) {
+			if let Err(err) = std::fs::remove_file(&self.0) {
+				log::warn!("Failed to remove Prometheus socket {}: {}", self.0.display(), err);
+			}
+		}
 	}
 }
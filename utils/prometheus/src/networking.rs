@@ -65,3 +65,57 @@ impl tokio::io::AsyncWrite for TcpStream {
 			.poll_close(cx)
 	}
 }
+
+/// Unix domain socket counterpart of [`Incoming`].
+#[cfg(unix)]
+pub struct UnixIncoming<'a>(pub async_std::os::unix::net::Incoming<'a>);
+
+#[cfg(unix)]
+impl hyper::server::accept::Accept for UnixIncoming<'_> {
+	type Conn = UnixStream;
+	type Error = async_std::io::Error;
+
+	fn poll_accept(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+		Pin::new(&mut Pin::into_inner(self).0)
+			.poll_next(cx)
+			.map(|opt| opt.map(|res| res.map(UnixStream)))
+	}
+}
+
+/// Unix domain socket counterpart of [`TcpStream`].
+#[cfg(unix)]
+pub struct UnixStream(pub async_std::os::unix::net::UnixStream);
+
+#[cfg(unix)]
+impl tokio::io::AsyncRead for UnixStream {
+	fn poll_read(
+		self: Pin<&mut Self>,
+		cx: &mut Context,
+		buf: &mut [u8]
+	) -> Poll<Result<usize, std::io::Error>> {
+		Pin::new(&mut Pin::into_inner(self).0)
+			.poll_read(cx, buf)
+	}
+}
+
+#[cfg(unix)]
+impl tokio::io::AsyncWrite for UnixStream {
+	fn poll_write(
+		self: Pin<&mut Self>,
+		cx: &mut Context,
+		buf: &[u8]
+	) -> Poll<Result<usize, std::io::Error>> {
+		Pin::new(&mut Pin::into_inner(self).0)
+			.poll_write(cx, buf)
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), std::io::Error>> {
+		Pin::new(&mut Pin::into_inner(self).0)
+			.poll_flush(cx)
+	}
+
+	fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), std::io::Error>> {
+		Pin::new(&mut Pin::into_inner(self).0)
+			.poll_close(cx)
+	}
+}
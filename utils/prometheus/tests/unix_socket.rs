@@ -0,0 +1,95 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(unix)]
+
+use async_std::{os::unix::net::UnixStream, prelude::*};
+use substrate_prometheus_endpoint::{init_prometheus_unix, register, Gauge, Registry, U64};
+use std::{path::{Path, PathBuf}, time::Duration};
+
+fn socket_path(name: &str) -> PathBuf {
+	let path = std::env::temp_dir()
+		.join(format!("substrate-prometheus-{}-{}.sock", name, std::process::id()));
+	let _ = std::fs::remove_file(&path);
+	path
+}
+
+fn registry() -> Registry {
+	let registry = Registry::new();
+	let gauge = register(Gauge::<U64>::new("test_gauge", "A test gauge").unwrap(), &registry)
+		.unwrap();
+	gauge.set(42);
+	registry
+}
+
+fn scrape(path: &Path) -> String {
+	async_std::task::block_on(async {
+		// The server binds asynchronously, so give it a few attempts.
+		let mut attempts = 0;
+		let mut stream = loop {
+			match UnixStream::connect(&path).await {
+				Ok(stream) => break stream,
+				Err(err) if attempts == 100 => panic!("could not connect: {}", err),
+				Err(_) => async_std::task::sleep(Duration::from_millis(10)).await,
+			}
+			attempts += 1;
+		};
+		stream.write_all(b"GET /metrics HTTP/1.0\r\nHost: localhost\r\n\r\n").await.unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).await.unwrap();
+		response
+	})
+}
+
+#[test]
+fn metrics_are_served_over_unix_socket() {
+	let path = socket_path("serve");
+	async_std::task::spawn(init_prometheus_unix(path.clone(), registry()));
+
+	let response = scrape(&path);
+	let _ = std::fs::remove_file(&path);
+
+	assert!(response.starts_with("HTTP/1.0 200 OK"), "unexpected response: {}", response);
+	assert!(response.contains("test_gauge 42"));
+}
+
+#[test]
+fn stale_socket_is_replaced() {
+	let path = socket_path("stale");
+	// Like a server that crashed, leave the socket file behind.
+	drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+	assert!(path.exists());
+
+	async_std::task::spawn(init_prometheus_unix(path.clone(), registry()));
+
+	let response = scrape(&path);
+	let _ = std::fs::remove_file(&path);
+
+	assert!(response.contains("test_gauge 42"));
+}
+
+#[test]
+fn socket_in_use_is_not_replaced() {
+	let path = socket_path("in-use");
+	let _listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+
+	let result = async_std::task::block_on(init_prometheus_unix(path.clone(), registry()));
+	let _ = std::fs::remove_file(&path);
+
+	let err = result.unwrap_err().to_string();
+	assert!(err.contains("already in use"), "unexpected error: {}", err);
+}